@@ -7,6 +7,7 @@ use fvm_ipld_blockstore::Block;
 use fvm_sdk as sdk;
 
 /// A blockstore that delegates to IPLD syscalls.
+#[derive(Default)]
 pub struct Blockstore;
 
 impl fvm_ipld_blockstore::Blockstore for Blockstore {
@@ -0,0 +1,159 @@
+use crate::uint256::Uint256;
+use fvm_ipld_hamt::Error as HamtError;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::ActorID;
+use std::fmt;
+
+/// Exit code for calling the constructor of a token that already exists.
+/// Actor-specific codes start at 32 to stay clear of the standard user codes.
+pub const ERR_ALREADY_CONSTRUCTED: ExitCode = ExitCode::new(32);
+
+/// Why a token operation was rejected. `State` methods return these instead of aborting;
+/// `invoke` maps each variant to a user exit code.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenError {
+    InsufficientBalance {
+        owner: ActorID,
+        balance: Uint256,
+        amount: Uint256,
+    },
+    InsufficientAllowance {
+        owner: ActorID,
+        operator: ActorID,
+        allowance: Uint256,
+        amount: Uint256,
+    },
+    Unauthorized {
+        caller: ActorID,
+        action: &'static str,
+    },
+    InvalidAmount {
+        amount: Uint256,
+        reason: &'static str,
+    },
+    Overflow,
+    /// Allowlist-only mode is on and the account isn't allowlisted.
+    NotAllowlisted(Address),
+    /// The actor is on the blocklist.
+    Frozen(ActorID),
+    /// The token is paused.
+    Paused,
+    /// The constructor was called again after the token was created.
+    AlreadyConstructed,
+    /// The constructor params describe a token we won't create.
+    InvalidConfig(&'static str),
+    /// The allowance would already have expired.
+    InvalidExpiry {
+        expiry: ChainEpoch,
+        epoch: ChainEpoch,
+    },
+    /// `approve_if` found a different allowance than the caller expected.
+    AllowanceMismatch {
+        expected: Uint256,
+        actual: Uint256,
+    },
+    /// Minting `amount` would take the circulating supply past the cap.
+    SupplyCapExceeded {
+        max_supply: Uint256,
+        supply: Uint256,
+        amount: Uint256,
+    },
+    /// FIL was attached to a method that doesn't take any.
+    UnexpectedValue(TokenAmount),
+    /// The actor's FIL balance would no longer cover the circulating supply.
+    InsufficientReserves {
+        reserves: TokenAmount,
+        supply: Uint256,
+    },
+    NotFound(Address),
+    /// The recipient's receiver hook refused the tokens.
+    ReceiverRejected {
+        to: ActorID,
+        exit_code: ExitCode,
+    },
+    /// The token state couldn't be read or written.
+    IllegalState(String),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::InsufficientBalance {
+                owner,
+                balance,
+                amount,
+            } => write!(
+                f,
+                "insufficient balance: {} holds {}, needs {}",
+                owner, balance, amount
+            ),
+            TokenError::InsufficientAllowance {
+                owner,
+                operator,
+                allowance,
+                amount,
+            } => write!(
+                f,
+                "insufficient allowance: {} may spend {} of {}'s tokens, needs {}",
+                operator, allowance, owner, amount
+            ),
+            TokenError::Unauthorized { caller, action } => {
+                write!(f, "{} is not authorized to {}", caller, action)
+            }
+            TokenError::InvalidAmount { amount, reason } => {
+                write!(f, "invalid amount {}: {}", amount, reason)
+            }
+            TokenError::Overflow => write!(f, "amount overflows 256 bits"),
+            TokenError::NotAllowlisted(address) => {
+                write!(f, "{} is not on the allowlist", address)
+            }
+            TokenError::Frozen(actor) => write!(f, "account {} is frozen", actor),
+            TokenError::Paused => write!(f, "token is paused"),
+            TokenError::AlreadyConstructed => write!(f, "token has already been constructed"),
+            TokenError::InvalidConfig(reason) => write!(f, "invalid token config: {}", reason),
+            TokenError::InvalidExpiry { expiry, epoch } => write!(
+                f,
+                "allowance expiry {} is before the current epoch {}",
+                expiry, epoch
+            ),
+            TokenError::AllowanceMismatch { expected, actual } => write!(
+                f,
+                "allowance changed: expected {}, found {}",
+                expected, actual
+            ),
+            TokenError::SupplyCapExceeded {
+                max_supply,
+                supply,
+                amount,
+            } => write!(
+                f,
+                "minting {} would exceed the max supply {} (circulating {})",
+                amount, max_supply, supply
+            ),
+            TokenError::InsufficientReserves { reserves, supply } => write!(
+                f,
+                "FIL reserves of {} don't cover the circulating supply {}",
+                reserves, supply
+            ),
+            TokenError::UnexpectedValue(value) => {
+                write!(f, "method doesn't accept FIL, got {}", value)
+            }
+            TokenError::NotFound(address) => write!(f, "failed to resolve address {}", address),
+            TokenError::ReceiverRejected { to, exit_code } => write!(
+                f,
+                "receiver hook of {} rejected the transfer: {:?}",
+                to, exit_code
+            ),
+            TokenError::IllegalState(msg) => write!(f, "illegal state: {}", msg),
+        }
+    }
+}
+
+impl From<HamtError> for TokenError {
+    fn from(err: HamtError) -> Self {
+        TokenError::IllegalState(err.to_string())
+    }
+}
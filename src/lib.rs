@@ -1,239 +1,1937 @@
 mod blockstore;
+mod error;
+mod method;
+mod receiver;
+mod role;
+mod runtime;
 mod state;
 mod types;
 mod uint256;
-use crate::blockstore::Blockstore;
-use cid::multihash::Code;
-use cid::Cid;
-use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
-use fvm_ipld_encoding::{to_vec, CborStore, RawBytes, DAG_CBOR};
+use error::{TokenError, ERR_ALREADY_CONSTRUCTED};
+use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
 use fvm_sdk as sdk;
 use fvm_sdk::message::NO_DATA_BLOCK_ID;
-use fvm_shared::bigint::BigUint;
-use fvm_shared::ActorID;
-use state::{State, Token};
-use std::collections::HashMap;
-use types::{Allowance, Approve, Mint, Transfer, TransferFrom};
-use uint256::Uint256;
+use fvm_shared::address::{Address, Protocol};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::{ActorID, METHOD_SEND};
+use method::Method;
+use receiver::{call_receiver_hook, FRC46TokenReceived};
+use role::Role;
+use runtime::{FvmRuntime, Runtime};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use state::{Recipient, State, Token};
+use types::{
+    AddMinter, AddToAllowlist, AdminBurn, Allowance, Approve, ApproveIf, Burn, BurnFrom,
+    ConstructorParams, DecreaseAllowance, Freeze, GrantRole, HasRole, IncreaseAllowance, Mint,
+    PausedReturn, ProposeOwner, RemoveFromAllowlist, RemoveMinter, RenounceRole, RevokeAllowance,
+    RevokeRole, SetAllowlistOnly, SetMetadata, Transfer, TransferFrom, Unfreeze, Withdraw,
+};
 #[macro_use]
 mod abort;
 
-#[no_mangle]
-pub fn invoke(params: Vec<u8>) -> u32 {
-    let ret: Option<RawBytes> = match sdk::message::method_number() {
-        1 => {
-            // create token(symbol,decimal,total_supply)
-            let mock_total_supply =
-                BigUint::parse_bytes(b"100000000000000000000000000000000", 10).unwrap();
-            let mock_total_supply = Uint256 {
-                big_uint: mock_total_supply,
-            };
-            let mock_token = Token {
-                symbol: "wfil".to_string(),
-                decimal: 18,
-                total_supply: mock_total_supply,
-            };
+/// ID of the init actor, the only actor allowed to call the constructor.
+const INIT_ACTOR_ID: ActorID = 1;
+
+// Only exported for the actor build, so native test binaries don't link against the syscalls.
+#[cfg_attr(not(test), no_mangle)]
+pub fn invoke(params: u32) -> u32 {
+    let rt = FvmRuntime::default();
+    let method = match Method::from_method_num(sdk::message::method_number()) {
+        Some(method) => method,
+        None => abort!(USR_UNHANDLED_MESSAGE, "unrecognized method"),
+    };
+    let ret = match dispatch(&rt, method, params) {
+        Ok(ret) => ret,
+        Err(err) => abort_with(&rt, err),
+    };
 
-            let params = serde_json::to_vec(&mock_token).unwrap();
+    match ret {
+        None => NO_DATA_BLOCK_ID,
+        Some(v) => match sdk::ipld::put_block(DAG_CBOR, v.bytes()) {
+            Ok(id) => id,
+            Err(err) => abort!(USR_SERIALIZATION, "failed to store return value: {}", err),
+        },
+    }
+}
+
+/// Runs a single method, returning its encoded return value.
+fn dispatch<RT: Runtime>(
+    rt: &RT,
+    method: Method,
+    params: u32,
+) -> Result<Option<RawBytes>, TokenError> {
+    // Only these methods account for attached FIL; anywhere else it would sit in the
+    // actor unbacked by tokens.
+    if !matches!(method, Method::Constructor | Method::Deposit | Method::Mint) {
+        let value = rt.value_received();
+        if value != TokenAmount::default() {
+            return Err(TokenError::UnexpectedValue(value));
+        }
+    }
+    let ret = match method {
+        Method::Constructor => {
+            if State::exists(rt)? {
+                return Err(TokenError::AlreadyConstructed);
+            }
+            let caller = rt.caller();
+            if caller != INIT_ACTOR_ID {
+                return Err(TokenError::Unauthorized {
+                    caller,
+                    action: "construct the token",
+                });
+            }
+            let params: ConstructorParams = deserialize_params(params);
+            let token = Token {
+                name: params.name,
+                symbol: params.symbol,
+                // Like FIL itself, any amount of base units can be moved.
+                granularity: 1,
+                decimal: params.decimals,
+                max_supply: params.max_supply,
+            };
+            let owner = resolve_address(rt, &params.owner)?;
+            let mut allocations = Vec::new();
+            for allocation in params.initial_allocations {
+                allocations.push((recipient(rt, &allocation.to)?, allocation.amount));
+            }
             let mut state = State::default();
-            let token: Token = serde_json::from_slice(&params).unwrap();
-            let result = state.constructor(token);
-            state.save();
+            // Allocations don't call receiver hooks: the token doesn't exist yet.
+            let result = state.constructor(rt, token, owner, params.allowlist_only, allocations)?;
+            state.save(rt)?;
             result
         }
-        2 => {
-            // mint
-            let mock_value = BigUint::parse_bytes(b"4546347290348029834222344344", 10).unwrap();
-            let mock_amount = Uint256 {
-                big_uint: mock_value,
-            };
-            unsafe {
-                let mock_actor = sdk::sys::message::caller().unwrap();
-                let mock_mint = Mint {
-                    actor: mock_actor,
-                    amount: mock_amount,
-                };
-
-                let params = serde_json::to_vec(&mock_mint).unwrap();
-                let mint = Mint::from_slice(&params).unwrap();
-                let mut state = State::load();
-                let res = state.mint(mint.actor, mint.amount);
-                state.save();
-                Some(RawBytes::new(res.to_bytes()))
+        Method::Mint => {
+            let mint: Mint = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let to = resolve_recipient(rt, &mut state, &mint.actor)?;
+            let mut res = state.mint(rt, to.clone(), mint.amount.clone())?;
+            state.save(rt)?;
+            if let Recipient::Id(to) = to {
+                res.recipient_data = call_receiver_hook(
+                    rt,
+                    FRC46TokenReceived {
+                        from: rt.receiver(),
+                        to,
+                        operator: rt.caller(),
+                        amount: mint.amount,
+                        operator_data: mint.operator_data,
+                        token_data: RawBytes::default(),
+                    },
+                )?;
             }
+            return_ipld(&res)
+        }
+        Method::BalanceOf => {
+            let address: Address = deserialize_params(params);
+            let state = State::load(rt)?;
+            // Tokens sent to the address before its actor existed may not be migrated yet.
+            let balance = match rt.resolve_address(&address) {
+                Some(actor) => {
+                    state.balance_of(rt, actor)? + state.pending_balance(rt, &address)?
+                }
+                None => state.pending_balance(rt, &address)?,
+            };
+            return_ipld(&balance)
         }
-        3 => {
-            //  actor balance
-            unsafe {
-                let mock_actor = sdk::sys::message::caller().unwrap();
-                let state = State::load();
-                let balance = state.balance_of(mock_actor);
-                Some(RawBytes::new(balance.to_string().as_bytes().to_vec()))
+        Method::Claim => {
+            let address: Address = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let balance = state.claim(rt, &address)?;
+            state.save(rt)?;
+            return_ipld(&balance)
+        }
+        Method::Allowance => {
+            let allowance: Allowance = deserialize_params(params);
+            let state = State::load(rt)?;
+            let owner = resolve_address(rt, &allowance.owner)?;
+            let operator = resolve_address(rt, &allowance.operator)?;
+            let amount = state.allowance(rt, &owner, &operator)?;
+            return_ipld(&amount)
+        }
+        Method::TransferFrom => {
+            let transfer_from: TransferFrom = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let owner = resolve_holder(rt, &mut state, &transfer_from.owner)?;
+            let to = resolve_recipient(rt, &mut state, &transfer_from.to)?;
+            let mut res =
+                state.transfer_from(rt, owner, to.clone(), transfer_from.amount.clone())?;
+            state.save(rt)?;
+            if let Recipient::Id(to) = to {
+                res.recipient_data = call_receiver_hook(
+                    rt,
+                    FRC46TokenReceived {
+                        from: owner,
+                        to,
+                        operator: rt.caller(),
+                        amount: transfer_from.amount,
+                        operator_data: transfer_from.operator_data,
+                        token_data: RawBytes::default(),
+                    },
+                )?;
             }
+            return_ipld(&res)
         }
-        4 => {
-            unsafe {
-                //  allowance
-                let mock_actor = sdk::sys::message::caller().unwrap();
-                let mock_approve = Allowance {
-                    from: mock_actor,
-                    to: 2u64,
-                };
-                let state = State::load();
-                let amount = state.allowance(&mock_approve.from, &mock_approve.to);
-                Some(RawBytes::new(amount.to_string().as_bytes().to_vec()))
+        Method::Transfer => {
+            let transfer: Transfer = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let to = resolve_recipient(rt, &mut state, &transfer.to)?;
+            let mut res = state.transfer(rt, to.clone(), transfer.amount.clone())?;
+            state.save(rt)?;
+            if let Recipient::Id(to) = to {
+                res.recipient_data = call_receiver_hook(
+                    rt,
+                    FRC46TokenReceived {
+                        from: rt.caller(),
+                        to,
+                        operator: rt.caller(),
+                        amount: transfer.amount,
+                        operator_data: transfer.operator_data,
+                        token_data: RawBytes::default(),
+                    },
+                )?;
             }
+            return_ipld(&res)
+        }
+        Method::Approve => {
+            let approve: Approve = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let actor = resolve_address(rt, &approve.actor)?;
+            let res = state.approve(rt, actor, approve.amount, approve.expiry)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::ApproveIf => {
+            let approve: ApproveIf = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let actor = resolve_address(rt, &approve.actor)?;
+            let res =
+                state.approve_if(rt, actor, approve.expected, approve.amount, approve.expiry)?;
+            state.save(rt)?;
+            return_ipld(&res)
         }
-        5 => {
-            unsafe {
-                // transfer_from
-                let mock_value = BigUint::parse_bytes(b"45463475445", 10).unwrap();
-                let mock_amount = Uint256 {
-                    big_uint: mock_value,
-                };
-                let mock_actor = sdk::sys::message::caller().unwrap();
-                let mock_transfer_from = TransferFrom {
-                    from: mock_actor,
-                    to: 2u64,
-                    amount: mock_amount,
-                };
-
-                let mut state = State::load();
-                let res = state.transfer_from(
-                    mock_transfer_from.from,
-                    mock_transfer_from.to,
-                    mock_transfer_from.amount,
-                );
-                state.save();
-                Some(RawBytes::new(res.to_bytes()))
+        Method::IncreaseAllowance => {
+            let increase: IncreaseAllowance = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let operator = resolve_address(rt, &increase.operator)?;
+            let res = state.increase_allowance(rt, operator, increase.increase)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::DecreaseAllowance => {
+            let decrease: DecreaseAllowance = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let operator = resolve_address(rt, &decrease.operator)?;
+            let res = state.decrease_allowance(rt, operator, decrease.decrease)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::RevokeAllowance => {
+            let revoke: RevokeAllowance = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let operator = resolve_address(rt, &revoke.operator)?;
+            let res = state.revoke_allowance(rt, operator)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::Burn => {
+            let burn: Burn = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let res = state.burn(rt, burn.amount)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::BurnFrom => {
+            let burn_from: BurnFrom = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let owner = resolve_holder(rt, &mut state, &burn_from.owner)?;
+            let res = state.burn_from(rt, owner, burn_from.amount)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::AdminBurn => {
+            let admin_burn: AdminBurn = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            // Moving a pending balance would fail for frozen owners, so only the balance
+            // already held under the ID is burned.
+            let owner = resolve_address(rt, &admin_burn.owner)?;
+            let res = state.admin_burn(rt, owner, admin_burn.amount)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::Deposit => {
+            let mut state = State::load(rt)?;
+            let (amount, mut res) = state.deposit(rt)?;
+            state.save(rt)?;
+            res.recipient_data = call_receiver_hook(
+                rt,
+                FRC46TokenReceived {
+                    from: rt.receiver(),
+                    to: rt.caller(),
+                    operator: rt.caller(),
+                    amount,
+                    operator_data: RawBytes::default(),
+                    token_data: RawBytes::default(),
+                },
+            )?;
+            return_ipld(&res)
+        }
+        Method::Withdraw => {
+            let withdraw: Withdraw = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let res = state.withdraw(rt, withdraw.amount.clone())?;
+            state.save(rt)?;
+            // The tokens are already burned; failing to pay out aborts and reverts the burn.
+            let to = Address::new_id(rt.caller());
+            let value = withdraw.amount.to_token_amount();
+            let receipt = rt
+                .send(&to, METHOD_SEND, RawBytes::default(), value)
+                .map_err(|err| TokenError::IllegalState(format!("failed to send FIL: {}", err)))?;
+            if !receipt.exit_code.is_success() {
+                return Err(TokenError::IllegalState(format!(
+                    "failed to send FIL to {}: {:?}",
+                    to, receipt.exit_code
+                )));
             }
+            return_ipld(&res)
         }
-        6 => {
-            // transfer
-
-            //let transfer = Transfer::from_slice(&params).unwrap();
-            let mock_value = BigUint::parse_bytes(b"454634729034802983", 10).unwrap();
-            let mock_amount = Uint256 {
-                big_uint: mock_value,
+        Method::Pause => {
+            let mut state = State::load(rt)?;
+            state.pause(rt)?;
+            state.save(rt)?;
+            None
+        }
+        Method::Unpause => {
+            let mut state = State::load(rt)?;
+            state.unpause(rt)?;
+            state.save(rt)?;
+            None
+        }
+        Method::Paused => {
+            let state = State::load(rt)?;
+            let (paused, changed_at) = state.paused();
+            return_ipld(&PausedReturn { paused, changed_at })
+        }
+        Method::Freeze => {
+            let freeze: Freeze = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let account = resolve_address(rt, &freeze.account)?;
+            state.freeze(rt, account)?;
+            state.save(rt)?;
+            None
+        }
+        Method::Unfreeze => {
+            let unfreeze: Unfreeze = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let account = resolve_address(rt, &unfreeze.account)?;
+            state.unfreeze(rt, account)?;
+            state.save(rt)?;
+            None
+        }
+        Method::IsFrozen => {
+            let address: Address = deserialize_params(params);
+            let state = State::load(rt)?;
+            let frozen = match rt.resolve_address(&address) {
+                Some(actor) => state.is_frozen(rt, actor)?,
+                None => false,
             };
-            let mock_transfer = Transfer {
-                to: 2u64,
-                amount: mock_amount,
+            return_ipld(&frozen)
+        }
+        Method::FrozenAccounts => {
+            let state = State::load(rt)?;
+            return_ipld(&state.frozen_accounts(rt)?)
+        }
+        Method::SetAllowlistOnly => {
+            let set: SetAllowlistOnly = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            state.set_allowlist_only(rt, set.enabled)?;
+            state.save(rt)?;
+            None
+        }
+        Method::AllowlistOnly => {
+            let state = State::load(rt)?;
+            return_ipld(&state.allowlist_only())
+        }
+        Method::AddToAllowlist => {
+            let add: AddToAllowlist = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let account = resolve_address(rt, &add.account)?;
+            state.add_to_allowlist(rt, account)?;
+            state.save(rt)?;
+            None
+        }
+        Method::RemoveFromAllowlist => {
+            let remove: RemoveFromAllowlist = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let account = resolve_address(rt, &remove.account)?;
+            state.remove_from_allowlist(rt, account)?;
+            state.save(rt)?;
+            None
+        }
+        Method::IsAllowlisted => {
+            let address: Address = deserialize_params(params);
+            let state = State::load(rt)?;
+            let allowed = match rt.resolve_address(&address) {
+                Some(actor) => state.is_allowlisted(rt, actor)?,
+                None => false,
             };
-            let mut state = State::load();
-            let res = state.transfer(mock_transfer.to, mock_transfer.amount);
-            state.save();
-            Some(RawBytes::new(res.to_bytes()))
-        }
-        7 => {
-            let mock_value = BigUint::parse_bytes(b"454634729034802983", 10).unwrap();
-            let mock_amount = Uint256 {
-                big_uint: mock_value,
+            return_ipld(&allowed)
+        }
+        Method::Name => {
+            let state = State::load(rt)?;
+            return_ipld(&state.name())
+        }
+        Method::Symbol => {
+            let state = State::load(rt)?;
+            return_ipld(&state.symbol())
+        }
+        Method::Decimals => {
+            let state = State::load(rt)?;
+            return_ipld(&state.decimal())
+        }
+        Method::SetMetadata => {
+            let metadata: SetMetadata = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            state.set_metadata(rt, metadata.name, metadata.symbol)?;
+            state.save(rt)?;
+            None
+        }
+        Method::Granularity => {
+            let state = State::load(rt)?;
+            return_ipld(&state.granularity())
+        }
+        Method::TotalSupply => {
+            let state = State::load(rt)?;
+            return_ipld(&state.total_supply())
+        }
+        Method::MaxSupply => {
+            let state = State::load(rt)?;
+            return_ipld(&state.max_supply())
+        }
+        Method::Owner => {
+            let state = State::load(rt)?;
+            return_ipld(&state.owner())
+        }
+        Method::PendingOwner => {
+            let state = State::load(rt)?;
+            return_ipld(&state.pending_owner())
+        }
+        Method::ProposeOwner => {
+            let propose: ProposeOwner = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let nominee = resolve_address(rt, &propose.nominee)?;
+            state.propose_owner(rt, nominee)?;
+            state.save(rt)?;
+            None
+        }
+        Method::AcceptOwnership => {
+            let mut state = State::load(rt)?;
+            state.accept_ownership(rt)?;
+            state.save(rt)?;
+            None
+        }
+        Method::CancelOwnershipProposal => {
+            let mut state = State::load(rt)?;
+            state.cancel_ownership_proposal(rt)?;
+            state.save(rt)?;
+            None
+        }
+        Method::RenounceOwnership => {
+            let mut state = State::load(rt)?;
+            state.renounce_ownership(rt)?;
+            state.save(rt)?;
+            None
+        }
+        Method::AddMinter => {
+            let add: AddMinter = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let minter = resolve_address(rt, &add.minter)?;
+            let res = state.add_minter(rt, minter)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::RemoveMinter => {
+            let remove: RemoveMinter = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let minter = resolve_address(rt, &remove.minter)?;
+            let res = state.remove_minter(rt, minter)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::IsMinter => {
+            let address: Address = deserialize_params(params);
+            let state = State::load(rt)?;
+            let is_minter = match rt.resolve_address(&address) {
+                Some(actor) => state.has_role(rt, Role::Minter, actor)?,
+                None => false,
             };
-            let mock_approve = Approve {
-                actor: 2u64,
-                amount: mock_amount,
+            return_ipld(&is_minter)
+        }
+        Method::GrantRole => {
+            let grant: GrantRole = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let account = resolve_address(rt, &grant.account)?;
+            let res = state.grant_role(rt, grant.role, account)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::RevokeRole => {
+            let revoke: RevokeRole = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let account = resolve_address(rt, &revoke.account)?;
+            let res = state.revoke_role(rt, revoke.role, account)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::RenounceRole => {
+            let renounce: RenounceRole = deserialize_params(params);
+            let mut state = State::load(rt)?;
+            let res = state.renounce_role(rt, renounce.role)?;
+            state.save(rt)?;
+            return_ipld(&res)
+        }
+        Method::HasRole => {
+            let has_role: HasRole = deserialize_params(params);
+            let state = State::load(rt)?;
+            let granted = match rt.resolve_address(&has_role.account) {
+                Some(actor) => state.has_role(rt, has_role.role, actor)?,
+                None => false,
             };
-            let mut state = State::load();
-            let res = state.approve(mock_approve.actor, mock_approve.amount);
-            state.save();
-            res
-        }
-        8 => {
-            // token symbol
-            let state = State::load();
-            Some(RawBytes::new(state.symbol().into_bytes()))
-        }
-        9 => {
-            //  token decimal
-            let state = State::load();
-            Some(RawBytes::new(state.decimal().to_be_bytes().to_vec()))
-        }
-        10 => {
-            //  token total_sypply
-            let state = State::load();
-            Some(RawBytes::new(state.total_supply().to_bytes_be()))
-        }
-        _ => abort!(USR_UNHANDLED_MESSAGE, "unrecognized method"),
+            return_ipld(&granted)
+        }
     };
+    Ok(ret)
+}
 
-    match ret {
-        None => NO_DATA_BLOCK_ID,
-        Some(v) => match sdk::ipld::put_block(DAG_CBOR, v.bytes()) {
-            Ok(id) => id,
-            Err(err) => abort!(USR_SERIALIZATION, "failed to store return value: {}", err),
+/// Aborts with the user exit code for a rejected token operation.
+/// This is the one place `TokenError`s are turned into exit codes.
+fn abort_with<RT: Runtime>(rt: &RT, err: TokenError) -> ! {
+    let code = match err {
+        TokenError::InsufficientBalance { .. }
+        | TokenError::InsufficientAllowance { .. }
+        | TokenError::InsufficientReserves { .. } => ExitCode::USR_INSUFFICIENT_FUNDS,
+        TokenError::Unauthorized { .. }
+        | TokenError::NotAllowlisted(_)
+        | TokenError::Frozen(_)
+        | TokenError::Paused
+        | TokenError::ReceiverRejected { .. } => ExitCode::USR_FORBIDDEN,
+        TokenError::InvalidAmount { .. }
+        | TokenError::InvalidConfig(_)
+        | TokenError::Overflow
+        | TokenError::InvalidExpiry { .. }
+        | TokenError::UnexpectedValue(_)
+        | TokenError::AllowanceMismatch { .. }
+        | TokenError::SupplyCapExceeded { .. } => ExitCode::USR_ILLEGAL_ARGUMENT,
+        TokenError::NotFound(_) => ExitCode::USR_NOT_FOUND,
+        TokenError::IllegalState(_) => ExitCode::USR_ILLEGAL_STATE,
+        TokenError::AlreadyConstructed => ERR_ALREADY_CONSTRUCTED,
+    };
+    rt.abort(code.value(), &err.to_string())
+}
+
+/// Encodes a method's return value as DAG-CBOR.
+/// Aborts with `USR_SERIALIZATION` if the value can't be encoded.
+fn return_ipld<T: Serialize + ?Sized>(value: &T) -> Option<RawBytes> {
+    match RawBytes::serialize(value) {
+        Ok(ret) => Some(ret),
+        Err(err) => abort!(
+            USR_SERIALIZATION,
+            "failed to serialize return value: {}",
+            err
+        ),
+    }
+}
+
+/// Resolves an address to the ID of the actor bound to it.
+fn resolve_address<RT: Runtime>(rt: &RT, address: &Address) -> Result<ActorID, TokenError> {
+    rt.resolve_address(address)
+        .ok_or(TokenError::NotFound(*address))
+}
+
+/// Resolves a holder's address to its actor ID, first moving any balance still pending
+/// under that address onto the ID. Only for holders about to be debited; operators and
+/// spenders go through `resolve_address` so allowance calls never touch balances.
+fn resolve_holder<RT: Runtime>(
+    rt: &RT,
+    state: &mut State,
+    address: &Address,
+) -> Result<ActorID, TokenError> {
+    let actor = resolve_address(rt, address)?;
+    state.migrate_pending(rt, address, actor)?;
+    Ok(actor)
+}
+
+/// Resolves the recipient of a mint or transfer. Key addresses with no actor yet
+/// receive a pending balance rather than failing.
+fn resolve_recipient<RT: Runtime>(
+    rt: &RT,
+    state: &mut State,
+    address: &Address,
+) -> Result<Recipient, TokenError> {
+    let to = recipient(rt, address)?;
+    if let Recipient::Id(actor) = to {
+        state.migrate_pending(rt, address, actor)?;
+    }
+    Ok(to)
+}
+
+/// Like `resolve_recipient`, but leaves any pending balance where it is.
+fn recipient<RT: Runtime>(rt: &RT, address: &Address) -> Result<Recipient, TokenError> {
+    match rt.resolve_address(address) {
+        Some(actor) => Ok(Recipient::Id(actor)),
+        None => match address.protocol() {
+            Protocol::Secp256k1 | Protocol::BLS => Ok(Recipient::Pending(*address)),
+            _ => Err(TokenError::NotFound(*address)),
         },
     }
 }
 
+/// Reads the parameters block passed to `invoke` and decodes it as DAG-CBOR.
+/// Aborts with `USR_SERIALIZATION` if the block is missing or malformed.
+fn deserialize_params<O: DeserializeOwned>(params: u32) -> O {
+    let (codec, raw) = match sdk::message::params_raw(params) {
+        Ok(params) => params,
+        Err(err) => abort!(USR_SERIALIZATION, "failed to receive params: {:?}", err),
+    };
+    if codec != DAG_CBOR {
+        abort!(USR_SERIALIZATION, "unsupported params codec: {}", codec);
+    }
+    match RawBytes::new(raw).deserialize() {
+        Ok(params) => params,
+        Err(err) => abort!(USR_SERIALIZATION, "failed to deserialize params: {}", err),
+    }
+}
+
 #[cfg(test)]
 mod uint256_test {
 
     use super::*;
+    use cid::multihash::{Code, MultihashDigest};
+    use cid::Cid;
+    use fvm_ipld_encoding::{to_vec, CborStore};
+    use fvm_shared::bigint::BigUint;
+    use runtime::MockRuntime;
+    use uint256::Uint256;
+    /// Gives the mock actor enough FIL to back every mint in these tests.
+    fn fund(rt: &MockRuntime) {
+        *rt.balance.borrow_mut() = TokenAmount::from(10u64).pow(30);
+    }
+
+    /// Mints like the `Mint` method: with the FIL backing the new tokens attached.
+    fn mint(
+        rt: &MockRuntime,
+        state: &mut State,
+        to: Recipient,
+        amount: Uint256,
+    ) -> Result<types::MintReturn, TokenError> {
+        *rt.value_received.borrow_mut() = amount.to_token_amount();
+        let res = state.mint(rt, to, amount);
+        *rt.value_received.borrow_mut() = TokenAmount::default();
+        res
+    }
+
+    fn test_token(max_supply: Uint256) -> Token {
+        Token {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            granularity: 1,
+            decimal: 18,
+            max_supply,
+        }
+    }
+
+    fn new_state(rt: &MockRuntime) -> State {
+        let token = test_token(Uint256::from_bytes_be(&[0xff; 32]));
+        fund(rt);
+        let mut state = State::default();
+        state
+            .constructor(rt, token, rt.caller(), false, Vec::new())
+            .unwrap();
+        state
+    }
+
     #[test]
     fn test_state() {
-        unsafe {
-            let actor: ActorID = 1u64;
-            let value = BigUint::parse_bytes(b"4546347290348029834222344344", 10).unwrap();
-            let amount = Uint256 {
-                big_uint: value.clone(),
-            };
+        let rt = MockRuntime::new(1000);
+        let actor: ActorID = 1u64;
+        rt.set_caller(actor);
+        let value = BigUint::parse_bytes(b"4546347290348029834222344344", 10).unwrap();
+        let amount = Uint256 {
+            big_uint: value.clone(),
+        };
 
-            let total_supply =
-                BigUint::parse_bytes(b"100000000000000000000000000000000", 10).unwrap();
-            let total_supply = Uint256 {
-                big_uint: total_supply.clone(),
-            };
-            let mut state = State::default();
+        let max_supply = BigUint::parse_bytes(b"100000000000000000000000000000000", 10).unwrap();
+        let max_supply = Uint256 {
+            big_uint: max_supply.clone(),
+        };
+        let mut state = State::default();
+        let token = Token {
+            name: "Wrapped FIL".to_string(),
+            symbol: "wfil".to_string(),
+            granularity: 1,
+            decimal: 18u64,
+            max_supply,
+        };
+        fund(&rt);
+        state
+            .constructor(&rt, token, actor, false, Vec::new())
+            .unwrap();
+        mint(&rt, &mut state, Recipient::Id(actor), amount.clone()).unwrap();
+        let balance = state.balance_of(&rt, actor).unwrap();
+        assert_eq!(balance, amount);
+
+        let to_actor: ActorID = 2u64;
+
+        let value = BigUint::parse_bytes(b"4546347290348029834222", 10).unwrap();
+        let amount = Uint256 {
+            big_uint: value.clone(),
+        };
+
+        let from_old_balance = state.balance_of(&rt, actor).unwrap();
+        let to_old_balance = state.balance_of(&rt, to_actor).unwrap();
+        state
+            .transfer(&rt, Recipient::Id(to_actor), amount.clone())
+            .unwrap();
+        let from_balance = state.balance_of(&rt, actor).unwrap();
+        let to_balance = state.balance_of(&rt, to_actor).unwrap();
+        assert_eq!(from_old_balance, from_balance + amount.clone());
+        assert_eq!(to_balance, to_old_balance + amount.clone());
+
+        let value = BigUint::parse_bytes(b"4546347290348029", 10).unwrap();
+        let amount = Uint256 {
+            big_uint: value.clone(),
+        };
+        state.approve(&rt, to_actor, amount.clone(), None).unwrap();
+
+        let allowance_balance = state.allowance(&rt, &actor, &to_actor).unwrap();
+        assert_eq!(allowance_balance, amount);
+
+        let value = BigUint::parse_bytes(b"454634729034", 10).unwrap();
+        let amount = Uint256 {
+            big_uint: value.clone(),
+        };
+        // the spender sends the owner's tokens to a third party
+        let third_actor: ActorID = 3u64;
+        let from_old_balance = state.balance_of(&rt, actor).unwrap();
+        let allowance_old = state.allowance(&rt, &actor, &to_actor).unwrap();
+        rt.set_caller(to_actor);
+        state
+            .transfer_from(&rt, actor, Recipient::Id(third_actor), amount.clone())
+            .unwrap();
+        let from_balance = state.balance_of(&rt, actor).unwrap();
+        let to_balance = state.balance_of(&rt, third_actor).unwrap();
+        let allowance = state.allowance(&rt, &actor, &to_actor).unwrap();
+        assert_eq!(from_old_balance, from_balance + amount.clone());
+        assert_eq!(to_balance, amount.clone());
+        assert_eq!(allowance_old, allowance + amount.clone());
+    }
+
+    #[test]
+    fn state_load_save_test() {
+        let rt = MockRuntime::new(1000);
+        let mut state = new_state(&rt);
+        state.token.symbol = "wfil".to_string();
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[42]),
+        )
+        .unwrap();
+        state.save(&rt).unwrap();
+
+        let state = State::load(&rt).unwrap();
+        assert_eq!(state.symbol(), "wfil");
+        assert_eq!(
+            state.balance_of(&rt, 1).unwrap(),
+            Uint256::from_bytes_be(&[42])
+        );
+    }
+
+    #[test]
+    fn token_errors_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        let res = state.transfer(&rt, Recipient::Id(2), Uint256::from_bytes_be(&[1]));
+        assert_eq!(
+            res.unwrap_err(),
+            TokenError::InsufficientBalance {
+                owner: 1,
+                balance: Uint256::default(),
+                amount: Uint256::from_bytes_be(&[1]),
+            }
+        );
+
+        // the allowance is there but the owner has no balance entry: this used to panic
+        state
+            .increase_allowance(&rt, 2, Uint256::from_bytes_be(&[1]))
+            .unwrap();
+        rt.set_caller(2);
+        let res = state.transfer_from(&rt, 1, Recipient::Id(3), Uint256::from_bytes_be(&[1]));
+        assert!(matches!(
+            res.unwrap_err(),
+            TokenError::InsufficientBalance { owner: 1, .. }
+        ));
+
+        // only the approved spender may move the owner's tokens
+        rt.set_caller(3);
+        let res = state.transfer_from(&rt, 1, Recipient::Id(3), Uint256::from_bytes_be(&[1]));
+        assert_eq!(
+            res.unwrap_err(),
+            TokenError::InsufficientAllowance {
+                owner: 1,
+                operator: 3,
+                allowance: Uint256::default(),
+                amount: Uint256::from_bytes_be(&[1]),
+            }
+        );
+        rt.set_caller(1);
+
+        let res = mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[1; 33]),
+        );
+        assert_eq!(res.unwrap_err(), TokenError::Overflow);
+    }
+
+    /// FRC-0042 method number derivation, used to check the dispatch table.
+    fn method_hash(name: &str) -> u64 {
+        if name == "Constructor" {
+            return 1;
+        }
+        let digest = Code::Blake2b512.digest(format!("1|{}", name).as_bytes());
+        digest
+            .digest()
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as u64)
+            .find(|num| *num >= 1 << 24)
+            .unwrap()
+    }
+
+    #[test]
+    fn method_numbers_test() {
+        for method in Method::ALL {
+            assert_eq!(method.method_num(), method_hash(method.name()));
+            assert_eq!(Method::from_method_num(method.method_num()), Some(*method));
+        }
+        assert_eq!(Method::Transfer.method_num(), 80475954);
+        assert_eq!(Method::from_method_num(2), None);
+    }
+
+    #[test]
+    fn burn_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[100]),
+        )
+        .unwrap();
+
+        let res = state.burn(&rt, Uint256::from_bytes_be(&[30])).unwrap();
+        assert_eq!(res.balance, Uint256::from_bytes_be(&[70]));
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[70]));
+        assert!(matches!(
+            state.burn(&rt, Uint256::from_bytes_be(&[71])).unwrap_err(),
+            TokenError::InsufficientBalance { owner: 1, .. }
+        ));
+
+        state
+            .increase_allowance(&rt, 2, Uint256::from_bytes_be(&[50]))
+            .unwrap();
+        rt.set_caller(2);
+        let res = state
+            .burn_from(&rt, 1, Uint256::from_bytes_be(&[20]))
+            .unwrap();
+        assert_eq!(res.balance, Uint256::from_bytes_be(&[50]));
+        assert_eq!(res.allowance, Uint256::from_bytes_be(&[30]));
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[50]));
+        assert!(matches!(
+            state
+                .burn_from(&rt, 1, Uint256::from_bytes_be(&[31]))
+                .unwrap_err(),
+            TokenError::InsufficientAllowance { operator: 2, .. }
+        ));
+
+        // the receipt is what the method returns
+        let ret = RawBytes::serialize(&res).unwrap();
+        let decoded: types::BurnFromReturn = ret.deserialize().unwrap();
+        assert_eq!(decoded.balance, Uint256::from_bytes_be(&[50]));
+    }
+
+    fn received(to: ActorID) -> FRC46TokenReceived {
+        FRC46TokenReceived {
+            from: 1,
+            to,
+            operator: 1,
+            amount: Uint256::from_bytes_be(&[1]),
+            operator_data: RawBytes::default(),
+            token_data: RawBytes::default(),
+        }
+    }
+
+    #[test]
+    fn receiver_hook_test() {
+        let rt = MockRuntime::new(1000);
+        // account actors have no hook and are never called, even if a send would fail
+        rt.accounts.borrow_mut().insert(2);
+        rt.send_exit_code.set(ExitCode::USR_UNHANDLED_MESSAGE);
+        call_receiver_hook(&rt, received(2)).unwrap();
+        assert!(rt.sent.borrow().is_empty());
+
+        rt.send_exit_code.set(ExitCode::OK);
+        call_receiver_hook(&rt, received(3)).unwrap();
+        let sent = rt.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, Address::new_id(3));
+        assert_eq!(sent[0].1, receiver::RECEIVER_HOOK_METHOD_NUM);
+    }
+
+    #[test]
+    fn receiver_hook_rejected_test() {
+        let rt = MockRuntime::new(1000);
+        rt.send_exit_code.set(ExitCode::USR_FORBIDDEN);
+        assert_eq!(
+            call_receiver_hook(&rt, received(3)).unwrap_err(),
+            TokenError::ReceiverRejected {
+                to: 3,
+                exit_code: ExitCode::USR_FORBIDDEN,
+            }
+        );
+    }
+
+    #[test]
+    fn wrapped_fil_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = State::default();
+        let token = test_token(Uint256::from_bytes_be(&[0xff; 32]));
+        state.constructor(&rt, token, 1, false, Vec::new()).unwrap();
+
+        // the deposited FIL is already part of the actor's balance
+        *rt.value_received.borrow_mut() = TokenAmount::from(100u64);
+        *rt.balance.borrow_mut() = TokenAmount::from(100u64);
+        let (amount, res) = state.deposit(&rt).unwrap();
+        assert_eq!(amount, Uint256::from_bytes_be(&[100]));
+        assert_eq!(res.balance, Uint256::from_bytes_be(&[100]));
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[100]));
+
+        // depositing nothing mints nothing and is rejected
+        *rt.value_received.borrow_mut() = TokenAmount::default();
+        assert_eq!(
+            state.deposit(&rt).unwrap_err(),
+            TokenError::InvalidAmount {
+                amount: Uint256::default(),
+                reason: "nothing was deposited",
+            }
+        );
+
+        // burning leaves the FIL behind, but minting can't use it: mints must attach FIL
+        rt.set_caller(1);
+        state.burn(&rt, Uint256::from_bytes_be(&[10])).unwrap();
+        assert_eq!(
+            state
+                .mint(&rt, Recipient::Id(1), Uint256::from_bytes_be(&[10]))
+                .unwrap_err(),
+            TokenError::InvalidAmount {
+                amount: Uint256::from_bytes_be(&[10]),
+                reason: "mints must attach exactly the minted amount of FIL",
+            }
+        );
+
+        let res = state.withdraw(&rt, Uint256::from_bytes_be(&[40])).unwrap();
+        assert_eq!(res.balance, Uint256::from_bytes_be(&[50]));
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[50]));
+
+        // reserves that were drained elsewhere block further withdrawals
+        *rt.balance.borrow_mut() = TokenAmount::from(40u64);
+        assert_eq!(
+            state
+                .withdraw(&rt, Uint256::from_bytes_be(&[10]))
+                .unwrap_err(),
+            TokenError::InsufficientReserves {
+                reserves: TokenAmount::from(40u64),
+                supply: Uint256::from_bytes_be(&[50]),
+            }
+        );
+    }
+
+    #[test]
+    fn unexpected_value_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        new_state(&rt).save(&rt).unwrap();
+
+        // FIL sent along with anything but a deposit or mint is refused up front
+        *rt.value_received.borrow_mut() = TokenAmount::from(5u64);
+        assert_eq!(
+            dispatch(&rt, Method::Transfer, 0).unwrap_err(),
+            TokenError::UnexpectedValue(TokenAmount::from(5u64))
+        );
+        assert_eq!(
+            dispatch(&rt, Method::Withdraw, 0).unwrap_err(),
+            TokenError::UnexpectedValue(TokenAmount::from(5u64))
+        );
+    }
+
+    #[test]
+    fn constructor_guard_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(2);
+        assert_eq!(
+            dispatch(&rt, Method::Constructor, 0).unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 2,
+                action: "construct the token",
+            }
+        );
+
+        // once the state exists, nobody can construct again
+        new_state(&rt).save(&rt).unwrap();
+        for caller in [INIT_ACTOR_ID, 2] {
+            rt.set_caller(caller);
+            assert_eq!(
+                dispatch(&rt, Method::Constructor, 0).unwrap_err(),
+                TokenError::AlreadyConstructed
+            );
+        }
+
+        // a root that doesn't hold a token state isn't mistaken for a fresh actor
+        let garbage = rt
+            .store()
+            .put_cbor(&"not a token", Code::Blake2b256)
+            .unwrap();
+        rt.set_root(&garbage).unwrap();
+        assert!(matches!(
+            State::exists(&rt),
+            Err(TokenError::IllegalState(_))
+        ));
+        let missing = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"missing"));
+        rt.set_root(&missing).unwrap();
+        assert!(matches!(
+            State::exists(&rt),
+            Err(TokenError::IllegalState(_))
+        ));
+    }
+
+    #[test]
+    fn constructor_test() {
+        let rt = MockRuntime::new(1000);
+        let address = Address::new_secp256k1(&[2u8; 65]).unwrap();
+        let allocations = vec![
+            (Recipient::Id(2), Uint256::from_bytes_be(&[30])),
+            (Recipient::Pending(address), Uint256::from_bytes_be(&[20])),
+        ];
+        *rt.balance.borrow_mut() = TokenAmount::from(50u64);
+        let mut state = State::default();
+        state
+            .constructor(
+                &rt,
+                test_token(Uint256::from_bytes_be(&[100])),
+                1,
+                false,
+                allocations,
+            )
+            .unwrap();
+        assert_eq!(state.owner(), Some(1));
+        assert_eq!(
+            state.balance_of(&rt, 2).unwrap(),
+            Uint256::from_bytes_be(&[30])
+        );
+        assert_eq!(
+            state.pending_balance(&rt, &address).unwrap(),
+            Uint256::from_bytes_be(&[20])
+        );
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[50]));
+
+        // allocations can't exceed the cap
+        let mut state = State::default();
+        let allocations = vec![(Recipient::Id(2), Uint256::from_bytes_be(&[101]))];
+        assert!(matches!(
+            state
+                .constructor(
+                    &rt,
+                    test_token(Uint256::from_bytes_be(&[100])),
+                    1,
+                    false,
+                    allocations
+                )
+                .unwrap_err(),
+            TokenError::SupplyCapExceeded { .. }
+        ));
+
+        let invalid = [
+            ("", "TEST", 18),
+            ("Test Token", "", 18),
+            ("Test Token", "WAY2LONGSYMBOL", 18),
+            ("Test Token", "T-T", 18),
+            ("Test Token", "TEST", 19),
+            ("Test Token", "TEST", 6),
+        ];
+        for (name, symbol, decimal) in invalid {
             let token = Token {
-                symbol: "wfil".to_string(),
-                decimal: 18u64,
-                total_supply: total_supply,
+                name: name.to_string(),
+                symbol: symbol.to_string(),
+                decimal,
+                ..test_token(Uint256::from_bytes_be(&[100]))
             };
-            state.constructor(token);
-            state.mint(actor, amount.clone());
-            let balance = state.balance_of(actor);
-            assert_eq!(balance, amount);
+            let mut state = State::default();
+            assert!(matches!(
+                state
+                    .constructor(&rt, token, 1, false, Vec::new())
+                    .unwrap_err(),
+                TokenError::InvalidConfig(_)
+            ));
+        }
 
-            let to_actor: ActorID = 2u64;
+        for max_supply in [Uint256::default(), Uint256::from_bytes_be(&[1; 33])] {
+            let mut state = State::default();
+            assert!(matches!(
+                state
+                    .constructor(&rt, test_token(max_supply), 1, false, Vec::new())
+                    .unwrap_err(),
+                TokenError::InvalidConfig(_)
+            ));
+        }
+    }
 
-            let value = BigUint::parse_bytes(b"4546347290348029834222", 10).unwrap();
-            let amount = Uint256 {
-                big_uint: value.clone(),
-            };
+    #[test]
+    fn supply_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = State::default();
+        let token = test_token(Uint256::from_bytes_be(&[100]));
+        fund(&rt);
+        state.constructor(&rt, token, 1, false, Vec::new()).unwrap();
+        assert_eq!(state.total_supply(), Uint256::default());
 
-            let from_old_balance = state.balance_of(actor);
-            let to_old_balance = state.balance_of(to_actor);
-            state.transfer(to_actor, amount.clone());
-            let from_balance = state.balance_of(actor);
-            let to_balance = state.balance_of(to_actor);
-            assert_eq!(from_old_balance, from_balance + amount.clone());
-            assert_eq!(to_balance, to_old_balance + amount.clone());
-
-            let value = BigUint::parse_bytes(b"4546347290348029", 10).unwrap();
-            let amount = Uint256 {
-                big_uint: value.clone(),
-            };
-            state.approve(to_actor, amount.clone());
+        let res = mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[60]),
+        )
+        .unwrap();
+        assert_eq!(res.supply, Uint256::from_bytes_be(&[60]));
+        let res = mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[41]),
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            TokenError::SupplyCapExceeded {
+                max_supply: Uint256::from_bytes_be(&[100]),
+                supply: Uint256::from_bytes_be(&[60]),
+                amount: Uint256::from_bytes_be(&[41]),
+            }
+        );
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[40]),
+        )
+        .unwrap();
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[100]));
 
-            let allowance_balance = state.allowance(&actor, &to_actor);
-            assert_eq!(allowance_balance, amount);
+        // burning frees room under the cap again
+        state.burn(&rt, Uint256::from_bytes_be(&[10])).unwrap();
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[90]));
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[10]),
+        )
+        .unwrap();
+        assert_eq!(state.max_supply(), state.total_supply());
+    }
 
-            let value = BigUint::parse_bytes(b"454634729034", 10).unwrap();
-            let amount = Uint256 {
-                big_uint: value.clone(),
-            };
-            let from_old_balance = state.balance_of(actor);
-            let to_old_balance = state.balance_of(to_actor);
-            let allowance_old = state.allowance(&actor, &to_actor);
-            state.transfer_from(actor, to_actor, amount.clone());
-            let from_balance = state.balance_of(actor);
-            let to_balance = state.balance_of(to_actor);
-            let allowance = state.allowance(&actor, &to_actor);
-            assert_eq!(from_old_balance, from_balance + amount.clone());
-            assert_eq!(to_balance, to_old_balance + amount.clone());
-            assert_eq!(allowance_old, allowance + amount.clone());
+    #[test]
+    fn minter_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        assert_eq!(state.owner(), Some(1));
+        assert!(state.has_role(&rt, Role::Minter, 1).unwrap());
+
+        rt.set_caller(2);
+        let res = mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[1]),
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 2,
+                action: "mint",
+            }
+        );
+        assert!(state.add_minter(&rt, 2).is_err());
+
+        rt.set_caller(1);
+        state.add_minter(&rt, 2).unwrap();
+        rt.set_caller(2);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[1]),
+        )
+        .unwrap();
+
+        rt.set_caller(1);
+        state.remove_minter(&rt, 2).unwrap();
+        assert!(!state.has_role(&rt, Role::Minter, 2).unwrap());
+        rt.set_caller(2);
+        assert!(mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[1])
+        )
+        .is_err());
+
+        // minters are managed by role admins, not by the owner as such
+        rt.set_caller(1);
+        state.grant_role(&rt, Role::Admin, 3).unwrap();
+        state.revoke_role(&rt, Role::Admin, 1).unwrap();
+        assert_eq!(
+            state.add_minter(&rt, 2).unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 1,
+                action: "add minters",
+            }
+        );
+        rt.set_caller(3);
+        state.add_minter(&rt, 2).unwrap();
+        assert!(state.has_role(&rt, Role::Minter, 2).unwrap());
+    }
+
+    #[test]
+    fn ownership_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+
+        // proposing doesn't move ownership until the nominee accepts
+        state.propose_owner(&rt, 2).unwrap();
+        assert_eq!(state.owner(), Some(1));
+        assert_eq!(state.pending_owner(), Some(2));
+        rt.set_caller(3);
+        assert!(state.accept_ownership(&rt).is_err());
+
+        rt.set_caller(1);
+        state.cancel_ownership_proposal(&rt).unwrap();
+        assert_eq!(state.pending_owner(), None);
+        rt.set_caller(2);
+        assert!(state.accept_ownership(&rt).is_err());
+
+        rt.set_caller(1);
+        state.propose_owner(&rt, 2).unwrap();
+        rt.set_caller(2);
+        state.accept_ownership(&rt).unwrap();
+        assert_eq!(state.owner(), Some(2));
+        assert_eq!(state.pending_owner(), None);
+        assert!(state.has_role(&rt, Role::Admin, 2).unwrap());
+        assert!(state.has_role(&rt, Role::Minter, 2).unwrap());
+
+        // the ex-owner loses the roles that came with ownership
+        rt.set_caller(1);
+        assert!(state.add_minter(&rt, 1).is_err());
+        assert_eq!(
+            state.grant_role(&rt, Role::Pauser, 1).unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 1,
+                action: "grant roles",
+            }
+        );
+        assert_eq!(
+            mint(
+                &rt,
+                &mut state,
+                Recipient::Id(1),
+                Uint256::from_bytes_be(&[1])
+            )
+            .unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 1,
+                action: "mint",
+            }
+        );
+
+        rt.set_caller(2);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[1]),
+        )
+        .unwrap();
+        state.renounce_ownership(&rt).unwrap();
+        assert_eq!(state.owner(), None);
+        assert!(!state.has_role(&rt, Role::Admin, 2).unwrap());
+        assert!(state.grant_role(&rt, Role::Pauser, 2).is_err());
+        assert!(mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[1])
+        )
+        .is_err());
+        assert_eq!(
+            state.add_minter(&rt, 2).unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 2,
+                action: "add minters",
+            }
+        );
+        assert!(state.propose_owner(&rt, 2).is_err());
+    }
+
+    #[test]
+    fn role_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        assert!(state.has_role(&rt, Role::Admin, 1).unwrap());
+        rt.events.borrow_mut().clear();
+
+        let granted = role::RoleChanged {
+            role: Role::Pauser,
+            account: 2,
+            sender: 1,
+            granted: true,
+        };
+        assert_eq!(
+            state.grant_role(&rt, Role::Pauser, 2).unwrap(),
+            Some(granted)
+        );
+        assert!(state.has_role(&rt, Role::Pauser, 2).unwrap());
+        assert!(!state.has_role(&rt, Role::Pauser, 3).unwrap());
+        // granting a role twice doesn't emit or return a second event
+        assert_eq!(state.grant_role(&rt, Role::Pauser, 2).unwrap(), None);
+        {
+            let events = rt.events.borrow();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].0, "RoleChanged");
+            let event: role::RoleChanged = events[0].1.deserialize().unwrap();
+            assert_eq!(
+                event,
+                role::RoleChanged {
+                    role: Role::Pauser,
+                    account: 2,
+                    sender: 1,
+                    granted: true,
+                }
+            );
         }
+
+        // only role admins may grant or revoke
+        rt.set_caller(2);
+        assert_eq!(
+            state.grant_role(&rt, Role::Minter, 2).unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 2,
+                action: "grant roles",
+            }
+        );
+        assert!(state.revoke_role(&rt, Role::Admin, 1).is_err());
+        assert_eq!(state.require_role(&rt, Role::Pauser, "pause").unwrap(), 2);
+
+        let renounced = state.renounce_role(&rt, Role::Pauser).unwrap().unwrap();
+        assert!(!renounced.granted);
+        assert_eq!(renounced.sender, 2);
+        assert!(!state.has_role(&rt, Role::Pauser, 2).unwrap());
+
+        rt.set_caller(1);
+        state.grant_role(&rt, Role::Burner, 3).unwrap();
+        state.revoke_role(&rt, Role::Burner, 3).unwrap();
+        assert!(!state.has_role(&rt, Role::Burner, 3).unwrap());
+
+        assert_eq!(Role::from_u8(1), Some(Role::Burner));
+        assert_eq!(Role::from_u8(4), Some(Role::MetadataAdmin));
+        assert_eq!(Role::from_u8(7), None);
+        assert_eq!(rt.events.borrow().len(), 4);
+    }
+
+    #[test]
+    fn admin_burn_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[100]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            state
+                .admin_burn(&rt, 2, Uint256::from_bytes_be(&[10]))
+                .unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 1,
+                action: "burn other holders' tokens",
+            }
+        );
+
+        // burners need no allowance, and frozen balances can still be burned
+        state.grant_role(&rt, Role::Burner, 1).unwrap();
+        state.grant_role(&rt, Role::Blocklister, 1).unwrap();
+        state.freeze(&rt, 2).unwrap();
+        let res = state
+            .admin_burn(&rt, 2, Uint256::from_bytes_be(&[10]))
+            .unwrap();
+        assert_eq!(res.balance, Uint256::from_bytes_be(&[90]));
+        assert_eq!(state.total_supply(), Uint256::from_bytes_be(&[90]));
+        assert!(state
+            .admin_burn(&rt, 2, Uint256::from_bytes_be(&[91]))
+            .is_err());
+    }
+
+    #[test]
+    fn metadata_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        assert_eq!(
+            state
+                .set_metadata(&rt, "Renamed".to_string(), "NEW".to_string())
+                .unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 1,
+                action: "change the token metadata",
+            }
+        );
+
+        state.grant_role(&rt, Role::MetadataAdmin, 1).unwrap();
+        assert_eq!(
+            state
+                .set_metadata(&rt, "Renamed".to_string(), "no spaces".to_string())
+                .unwrap_err(),
+            TokenError::InvalidConfig("symbol must be ASCII letters and digits")
+        );
+        state
+            .set_metadata(&rt, "Renamed".to_string(), "NEW".to_string())
+            .unwrap();
+        assert_eq!(state.name(), "Renamed");
+        assert_eq!(state.symbol(), "NEW");
+    }
+
+    #[test]
+    fn approve_if_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[100]),
+        )
+        .unwrap();
+        state
+            .approve(&rt, 2, Uint256::from_bytes_be(&[10]), None)
+            .unwrap();
+
+        // the spender used part of the allowance in between
+        rt.set_caller(2);
+        state
+            .transfer_from(&rt, 1, Recipient::Id(2), Uint256::from_bytes_be(&[4]))
+            .unwrap();
+        rt.set_caller(1);
+        assert_eq!(
+            state
+                .approve_if(
+                    &rt,
+                    2,
+                    Uint256::from_bytes_be(&[10]),
+                    Uint256::from_bytes_be(&[20]),
+                    None
+                )
+                .unwrap_err(),
+            TokenError::AllowanceMismatch {
+                expected: Uint256::from_bytes_be(&[10]),
+                actual: Uint256::from_bytes_be(&[6]),
+            }
+        );
+        state
+            .approve_if(
+                &rt,
+                2,
+                Uint256::from_bytes_be(&[6]),
+                Uint256::from_bytes_be(&[20]),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            state.allowance(&rt, &1, &2).unwrap(),
+            Uint256::from_bytes_be(&[20])
+        );
+
+        // decreasing saturates at zero
+        assert_eq!(
+            state
+                .decrease_allowance(&rt, 2, Uint256::from_bytes_be(&[30]))
+                .unwrap(),
+            Uint256::default()
+        );
+    }
+
+    #[test]
+    fn unlimited_allowance_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+
+        // approving more than the balance is fine; the spend is what gets checked
+        state
+            .approve(&rt, 2, state::unlimited_allowance(), None)
+            .unwrap();
+        rt.set_caller(2);
+        assert!(matches!(
+            state
+                .transfer_from(&rt, 1, Recipient::Id(3), Uint256::from_bytes_be(&[1]))
+                .unwrap_err(),
+            TokenError::InsufficientBalance { owner: 1, .. }
+        ));
+
+        rt.set_caller(1);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[100]),
+        )
+        .unwrap();
+        rt.set_caller(2);
+        let res = state
+            .transfer_from(&rt, 1, Recipient::Id(3), Uint256::from_bytes_be(&[60]))
+            .unwrap();
+        assert_eq!(res.allowance, state::unlimited_allowance());
+        state
+            .burn_from(&rt, 1, Uint256::from_bytes_be(&[40]))
+            .unwrap();
+        assert_eq!(
+            state.allowance(&rt, &1, &2).unwrap(),
+            state::unlimited_allowance()
+        );
+
+        // nothing can be approved beyond the unlimited allowance
+        rt.set_caller(1);
+        let too_wide = Uint256::from_bytes_be(&[1; 33]);
+        assert_eq!(
+            state.approve(&rt, 4, too_wide.clone(), None).unwrap_err(),
+            TokenError::Overflow
+        );
+        assert_eq!(
+            state
+                .approve_if(&rt, 4, Uint256::default(), too_wide, None)
+                .unwrap_err(),
+            TokenError::Overflow
+        );
+
+        // increasing leaves it unlimited instead of overflowing
+        assert_eq!(
+            state
+                .increase_allowance(&rt, 2, Uint256::from_bytes_be(&[5]))
+                .unwrap(),
+            state::unlimited_allowance()
+        );
+
+        // decreasing would silently turn it finite, so it is refused
+        assert_eq!(
+            state
+                .decrease_allowance(&rt, 2, Uint256::from_bytes_be(&[5]))
+                .unwrap_err(),
+            TokenError::InvalidAmount {
+                amount: Uint256::from_bytes_be(&[5]),
+                reason: "an unlimited allowance can only be revoked or re-approved",
+            }
+        );
+        assert_eq!(
+            state.allowance(&rt, &1, &2).unwrap(),
+            state::unlimited_allowance()
+        );
+        state.revoke_allowance(&rt, 2).unwrap();
+        assert_eq!(state.allowance(&rt, &1, &2).unwrap(), Uint256::default());
+
+        // decreasing by the whole allowance still saturates at zero
+        state
+            .approve(&rt, 2, state::unlimited_allowance(), None)
+            .unwrap();
+        assert_eq!(
+            state
+                .decrease_allowance(&rt, 2, state::unlimited_allowance())
+                .unwrap(),
+            Uint256::default()
+        );
+        assert_eq!(state.allowance(&rt, &1, &2).unwrap(), Uint256::default());
+    }
+
+    #[test]
+    fn allowance_expiry_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[100]),
+        )
+        .unwrap();
+        let empty = state.allowance;
+
+        rt.epoch.set(10);
+        assert_eq!(
+            state
+                .approve(&rt, 2, Uint256::from_bytes_be(&[50]), Some(9))
+                .unwrap_err(),
+            TokenError::InvalidExpiry {
+                expiry: 9,
+                epoch: 10
+            }
+        );
+        state
+            .approve(&rt, 2, Uint256::from_bytes_be(&[50]), Some(20))
+            .unwrap();
+        state
+            .approve(&rt, 3, Uint256::from_bytes_be(&[50]), Some(30))
+            .unwrap();
+
+        // spendable up to and including the expiry epoch, and spending keeps the expiry
+        rt.epoch.set(20);
+        rt.set_caller(2);
+        state
+            .transfer_from(&rt, 1, Recipient::Id(2), Uint256::from_bytes_be(&[10]))
+            .unwrap();
+        assert_eq!(
+            state.allowance_entry(&rt, 1, 2).unwrap().unwrap().expiry,
+            Some(20)
+        );
+
+        rt.epoch.set(21);
+        assert_eq!(state.allowance(&rt, &1, &2).unwrap(), Uint256::default());
+        assert!(matches!(
+            state
+                .transfer_from(&rt, 1, Recipient::Id(2), Uint256::from_bytes_be(&[1]))
+                .unwrap_err(),
+            TokenError::InsufficientAllowance { operator: 2, .. }
+        ));
+
+        // the owner's expired entries are pruned the next time its allowances change
+        rt.epoch.set(31);
+        rt.set_caller(1);
+        state
+            .increase_allowance(&rt, 4, Uint256::from_bytes_be(&[1]))
+            .unwrap();
+        state.revoke_allowance(&rt, 4).unwrap();
+        assert_eq!(state.allowance, empty);
+    }
+
+    #[test]
+    fn pause_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(1),
+            Uint256::from_bytes_be(&[100]),
+        )
+        .unwrap();
+        state
+            .approve(&rt, 2, Uint256::from_bytes_be(&[50]), None)
+            .unwrap();
+        assert!(state.pause(&rt).is_err());
+        state.grant_role(&rt, Role::Pauser, 1).unwrap();
+
+        rt.epoch.set(7);
+        state.pause(&rt).unwrap();
+        assert_eq!(state.paused(), (true, 7));
+        let one = || Uint256::from_bytes_be(&[1]);
+        assert_eq!(
+            mint(&rt, &mut state, Recipient::Id(1), one()).unwrap_err(),
+            TokenError::Paused
+        );
+        assert_eq!(
+            state.transfer(&rt, Recipient::Id(2), one()).unwrap_err(),
+            TokenError::Paused
+        );
+        assert_eq!(state.burn(&rt, one()).unwrap_err(), TokenError::Paused);
+        assert_eq!(
+            state.approve(&rt, 3, one(), None).unwrap_err(),
+            TokenError::Paused
+        );
+        rt.set_caller(2);
+        assert_eq!(
+            state
+                .transfer_from(&rt, 1, Recipient::Id(2), one())
+                .unwrap_err(),
+            TokenError::Paused
+        );
+        // reads keep working
+        assert_eq!(
+            state.balance_of(&rt, 1).unwrap(),
+            Uint256::from_bytes_be(&[100])
+        );
+
+        rt.set_caller(1);
+        rt.epoch.set(9);
+        state.unpause(&rt).unwrap();
+        assert_eq!(state.paused(), (false, 9));
+        state.transfer(&rt, Recipient::Id(2), one()).unwrap();
+    }
+
+    #[test]
+    fn freeze_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Id(2),
+            Uint256::from_bytes_be(&[100]),
+        )
+        .unwrap();
+        assert!(state.freeze(&rt, 2).is_err());
+        state.grant_role(&rt, Role::Blocklister, 1).unwrap();
+        rt.set_caller(2);
+        state
+            .approve(&rt, 3, Uint256::from_bytes_be(&[50]), None)
+            .unwrap();
+
+        rt.set_caller(1);
+        state.freeze(&rt, 2).unwrap();
+        state.freeze(&rt, 4).unwrap();
+        assert!(state.is_frozen(&rt, 2).unwrap());
+        assert!(!state.is_frozen(&rt, 3).unwrap());
+        let mut frozen = state.frozen_accounts(&rt).unwrap();
+        frozen.sort();
+        assert_eq!(frozen, vec![2, 4]);
+
+        let one = || Uint256::from_bytes_be(&[1]);
+        // can't receive
+        assert_eq!(
+            mint(&rt, &mut state, Recipient::Id(2), one()).unwrap_err(),
+            TokenError::Frozen(2)
+        );
+        // can't send, directly or through a spender
+        rt.set_caller(2);
+        assert_eq!(
+            state.transfer(&rt, Recipient::Id(3), one()).unwrap_err(),
+            TokenError::Frozen(2)
+        );
+        assert_eq!(
+            state.approve(&rt, 3, one(), None).unwrap_err(),
+            TokenError::Frozen(2)
+        );
+        rt.set_caller(3);
+        assert_eq!(
+            state
+                .transfer_from(&rt, 2, Recipient::Id(3), one())
+                .unwrap_err(),
+            TokenError::Frozen(2)
+        );
+
+        // frozen spenders can't spend either
+        rt.set_caller(1);
+        state.unfreeze(&rt, 2).unwrap();
+        state.freeze(&rt, 3).unwrap();
+        rt.set_caller(3);
+        assert_eq!(
+            state
+                .transfer_from(&rt, 2, Recipient::Id(5), one())
+                .unwrap_err(),
+            TokenError::Frozen(3)
+        );
+        rt.set_caller(2);
+        state.transfer(&rt, Recipient::Id(5), one()).unwrap();
+    }
+
+    #[test]
+    fn allowlist_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        fund(&rt);
+        let mut state = State::default();
+        let allocations = vec![(Recipient::Id(2), Uint256::from_bytes_be(&[100]))];
+        state
+            .constructor(
+                &rt,
+                test_token(Uint256::from_bytes_be(&[0xff; 32])),
+                1,
+                true,
+                allocations,
+            )
+            .unwrap();
+        assert!(state.allowlist_only());
+        assert!(state.is_allowlisted(&rt, 1).unwrap());
+        assert!(state.is_allowlisted(&rt, 2).unwrap());
+
+        let one = || Uint256::from_bytes_be(&[1]);
+        rt.set_caller(2);
+        assert_eq!(
+            state.transfer(&rt, Recipient::Id(3), one()).unwrap_err(),
+            TokenError::NotAllowlisted(Address::new_id(3))
+        );
+        let address = Address::new_secp256k1(&[3u8; 65]).unwrap();
+        assert_eq!(
+            state
+                .transfer(&rt, Recipient::Pending(address), one())
+                .unwrap_err(),
+            TokenError::NotAllowlisted(address)
+        );
+        state.transfer(&rt, Recipient::Id(1), one()).unwrap();
+
+        rt.set_caller(1);
+        assert!(state.add_to_allowlist(&rt, 3).is_err());
+        state.grant_role(&rt, Role::AllowlistAdmin, 1).unwrap();
+        state.add_to_allowlist(&rt, 3).unwrap();
+        rt.set_caller(2);
+        state.transfer(&rt, Recipient::Id(3), one()).unwrap();
+
+        // the sender has to be allowlisted too
+        rt.set_caller(1);
+        state.remove_from_allowlist(&rt, 2).unwrap();
+        rt.set_caller(2);
+        assert_eq!(
+            state.burn(&rt, one()).unwrap_err(),
+            TokenError::NotAllowlisted(Address::new_id(2))
+        );
+
+        rt.set_caller(1);
+        state.set_allowlist_only(&rt, false).unwrap();
+        rt.set_caller(2);
+        state.transfer(&rt, Recipient::Id(4), one()).unwrap();
+    }
+
+    #[test]
+    fn allowance_table_test() {
+        let rt = MockRuntime::new(1000);
+        let mut state = new_state(&rt);
+        let empty = state.allowance;
+
+        rt.set_caller(1);
+        state
+            .increase_allowance(&rt, 2, Uint256::from_bytes_be(&[5]))
+            .unwrap();
+        state
+            .increase_allowance(&rt, 3, Uint256::from_bytes_be(&[7]))
+            .unwrap();
+        assert_eq!(
+            state.allowance(&rt, &1, &2).unwrap(),
+            Uint256::from_bytes_be(&[5])
+        );
+        assert_eq!(
+            state.allowance(&rt, &1, &3).unwrap(),
+            Uint256::from_bytes_be(&[7])
+        );
+        assert_eq!(state.allowance(&rt, &2, &1).unwrap(), Uint256::default());
+
+        // revoking the last spender deletes the owner's sub-map entirely
+        state.revoke_allowance(&rt, 2).unwrap();
+        assert_ne!(state.allowance, empty);
+        state
+            .decrease_allowance(&rt, 3, Uint256::from_bytes_be(&[9]))
+            .unwrap();
+        assert_eq!(state.allowance, empty);
+    }
+
+    #[test]
+    fn pending_balance_test() {
+        let rt = MockRuntime::new(1000);
+        let mut state = new_state(&rt);
+        let address = Address::new_secp256k1(&[1u8; 65]).unwrap();
+        let amount = Uint256 {
+            big_uint: BigUint::from(100u64),
+        };
+
+        mint(&rt, &mut state, Recipient::Pending(address), amount.clone()).unwrap();
+        mint(&rt, &mut state, Recipient::Pending(address), amount.clone()).unwrap();
+        assert_eq!(
+            state.pending_balance(&rt, &address).unwrap(),
+            amount.clone() + amount.clone()
+        );
+
+        let actor: ActorID = 100u64;
+        state.migrate_pending(&rt, &address, actor).unwrap();
+        assert_eq!(
+            state.pending_balance(&rt, &address).unwrap(),
+            Uint256::default()
+        );
+        assert_eq!(
+            state.balance_of(&rt, actor).unwrap(),
+            amount.clone() + amount.clone()
+        );
+    }
+
+    #[test]
+    fn claim_test() {
+        let rt = MockRuntime::new(1000);
+        rt.set_caller(1);
+        let mut state = new_state(&rt);
+        let address = Address::new_secp256k1(&[1u8; 65]).unwrap();
+        mint(
+            &rt,
+            &mut state,
+            Recipient::Pending(address),
+            Uint256::from_bytes_be(&[10]),
+        )
+        .unwrap();
+
+        // the wallet's actor now exists, but its airdrop is still pending
+        rt.addresses.borrow_mut().insert(address, 100);
+        rt.set_caller(100);
+        assert!(matches!(
+            state.transfer(&rt, Recipient::Id(2), Uint256::from_bytes_be(&[4])),
+            Err(TokenError::InsufficientBalance { .. })
+        ));
+
+        // only the actor the address resolves to may claim it
+        rt.set_caller(2);
+        assert_eq!(
+            state.claim(&rt, &address).unwrap_err(),
+            TokenError::Unauthorized {
+                caller: 2,
+                action: "claim the pending balance",
+            }
+        );
+
+        rt.set_caller(100);
+        assert_eq!(
+            state.claim(&rt, &address).unwrap(),
+            Uint256::from_bytes_be(&[10])
+        );
+        state
+            .transfer(&rt, Recipient::Id(2), Uint256::from_bytes_be(&[4]))
+            .unwrap();
+        state.burn(&rt, Uint256::from_bytes_be(&[1])).unwrap();
+        assert_eq!(
+            state.balance_of(&rt, 100).unwrap(),
+            Uint256::from_bytes_be(&[5])
+        );
+        assert_eq!(
+            state.pending_balance(&rt, &address).unwrap(),
+            Uint256::default()
+        );
     }
 
     #[test]
@@ -244,8 +1942,8 @@ mod uint256_test {
             big_uint: value.clone(),
         };
 
-        let serde_value = serde_json::to_vec(&u1).unwrap();
-        let uint256: Uint256 = serde_json::from_slice(&serde_value).unwrap();
+        let serde_value = to_vec(&u1).unwrap();
+        let uint256: Uint256 = fvm_ipld_encoding::from_slice(&serde_value).unwrap();
 
         assert_eq!(uint256.big_uint, value);
 
@@ -255,10 +1953,21 @@ mod uint256_test {
             big_uint: value.clone(),
         };
 
-        let serde_value = serde_json::to_vec(&u1).unwrap();
-        let uint256: Uint256 = serde_json::from_slice(&serde_value).unwrap();
+        let serde_value = to_vec(&u1).unwrap();
+        let uint256: Uint256 = fvm_ipld_encoding::from_slice(&serde_value).unwrap();
 
         assert_eq!(uint256.big_uint, value);
+
+        // zero encodes as an empty CBOR byte string, as Filecoin bignums do
+        let serde_value = to_vec(&Uint256::default()).unwrap();
+        assert_eq!(serde_value, vec![0x40]);
+
+        // amounts wider than 256 bits don't decode
+        let serde_value = to_vec(&Uint256::from_bytes_be(&[1; 33])).unwrap();
+        assert!(fvm_ipld_encoding::from_slice::<Uint256>(&serde_value).is_err());
+        let serde_value = to_vec(&Uint256::max_value()).unwrap();
+        let uint256: Uint256 = fvm_ipld_encoding::from_slice(&serde_value).unwrap();
+        assert_eq!(uint256, Uint256::max_value());
     }
 
     #[test]
@@ -279,7 +1988,7 @@ mod uint256_test {
         let c = b.clone() - a.clone();
         assert_eq!(u3.big_uint, c);
 
-        assert_eq!(u3 < u2, true);
-        assert_eq!(u3 > u2, false);
+        assert!(u3 < u2);
+        assert!(u3 <= u2);
     }
 }
@@ -0,0 +1,93 @@
+use fvm_shared::MethodNum;
+
+/// Generates the `Method` enum together with its dispatch table.
+/// Every exported method is listed once with its FRC-0042 method number,
+/// i.e. the first 4-byte chunk of `blake2b-512("1|" + name)` that is at least `1 << 24`.
+macro_rules! methods {
+    ($($name:ident = $num:expr,)*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Method {
+            $($name,)*
+        }
+
+        impl Method {
+            /// Every exported method, in declaration order.
+            #[cfg(test)]
+            pub const ALL: &'static [Method] = &[$(Method::$name,)*];
+
+            pub fn from_method_num(num: MethodNum) -> Option<Self> {
+                match num {
+                    $($num => Some(Method::$name),)*
+                    _ => None,
+                }
+            }
+
+            #[cfg(test)]
+            pub fn method_num(self) -> MethodNum {
+                match self {
+                    $(Method::$name => $num,)*
+                }
+            }
+
+            #[cfg(test)]
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Method::$name => stringify!($name),)*
+                }
+            }
+        }
+    };
+}
+
+// The constructor keeps the reserved method number 1.
+methods! {
+    Constructor = 1,
+    Mint = 0x06f84ab2,
+    BalanceOf = 0x8710e1ac,
+    Allowance = 0xfaa45236,
+    TransferFrom = 0xd7d4deed,
+    Transfer = 0x04cbf732,
+    Approve = 0x4cd54055,
+    Symbol = 0x7adab63e,
+    Decimals = 0x5d68ff77,
+    TotalSupply = 0x06da7a35,
+    Name = 0x02ea015c,
+    Granularity = 0xeaa64da5,
+    IncreaseAllowance = 0x69ecb918,
+    DecreaseAllowance = 0x5b286f21,
+    RevokeAllowance = 0xa4d840b1,
+    Burn = 0x5584159a,
+    BurnFrom = 0xb19a37a2,
+    MaxSupply = 0x4f9b912a,
+    Owner = 0x2c1a0b1d,
+    AddMinter = 0x140c2a19,
+    RemoveMinter = 0x4e68f60b,
+    IsMinter = 0x91461ba8,
+    GrantRole = 0xd3f8f44b,
+    RevokeRole = 0x22940a36,
+    RenounceRole = 0xec02bb0e,
+    HasRole = 0x3bb260a6,
+    PendingOwner = 0xf1013afb,
+    ProposeOwner = 0x28d8406f,
+    AcceptOwnership = 0xf20c7fe6,
+    CancelOwnershipProposal = 0x5f86ca6f,
+    RenounceOwnership = 0xc1ade122,
+    Deposit = 0x73301619,
+    Withdraw = 0x32e7cdb6,
+    ApproveIf = 0x04dd666f,
+    Pause = 0x0ab827d3,
+    Unpause = 0xff55bf1f,
+    Paused = 0xc9b7fbc2,
+    Freeze = 0x26f0050d,
+    Unfreeze = 0x972f4872,
+    IsFrozen = 0x5d56571f,
+    FrozenAccounts = 0xd38c4755,
+    SetAllowlistOnly = 0x5fd8d474,
+    AllowlistOnly = 0xc124e6f7,
+    AddToAllowlist = 0x0aca1921,
+    RemoveFromAllowlist = 0x0757a7c2,
+    IsAllowlisted = 0xf429983d,
+    Claim = 0xf121db25,
+    AdminBurn = 0x0f82f469,
+    SetMetadata = 0xffcb7c90,
+}
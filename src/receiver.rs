@@ -0,0 +1,72 @@
+use crate::error::TokenError;
+use crate::runtime::Runtime;
+use crate::uint256::Uint256;
+use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::{ActorID, MethodNum};
+
+/// FRC-0042 method number of the universal receiver hook ("Receive").
+pub const RECEIVER_HOOK_METHOD_NUM: MethodNum = 3726118371;
+
+/// FRC-0042 hash of "FRC46", telling the receiver hook that the payload is a token transfer.
+pub const FRC46_TOKEN_TYPE: u32 = 2233613279;
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct UniversalReceiverParams {
+    pub type_: u32,
+    pub payload: RawBytes,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct FRC46TokenReceived {
+    pub from: ActorID,
+    pub to: ActorID,
+    pub operator: ActorID,
+    pub amount: Uint256,
+    pub operator_data: RawBytes,
+    pub token_data: RawBytes,
+}
+
+/// Notifies the recipient of incoming tokens and returns whatever data its hook returned.
+/// The state must already be saved: the recipient may call back into the token.
+/// Fails, reverting the whole transfer, if the recipient rejects the tokens.
+/// Account actors have no hook and always accept.
+pub fn call_receiver_hook<RT: Runtime>(
+    rt: &RT,
+    received: FRC46TokenReceived,
+) -> Result<RawBytes, TokenError> {
+    if rt.is_account(received.to) {
+        return Ok(RawBytes::default());
+    }
+    let to = received.to;
+    let payload = RawBytes::serialize(&received).map_err(|err| {
+        TokenError::IllegalState(format!("failed to serialize hook payload: {}", err))
+    })?;
+    let params = UniversalReceiverParams {
+        type_: FRC46_TOKEN_TYPE,
+        payload,
+    };
+    let params = RawBytes::serialize(&params).map_err(|err| {
+        TokenError::IllegalState(format!("failed to serialize hook params: {}", err))
+    })?;
+
+    let receipt = rt
+        .send(
+            &Address::new_id(to),
+            RECEIVER_HOOK_METHOD_NUM,
+            params,
+            TokenAmount::default(),
+        )
+        .map_err(|err| {
+            TokenError::IllegalState(format!("failed to call receiver hook: {}", err))
+        })?;
+    if !receipt.exit_code.is_success() {
+        return Err(TokenError::ReceiverRejected {
+            to,
+            exit_code: receipt.exit_code,
+        });
+    }
+    Ok(receipt.return_data)
+}
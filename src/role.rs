@@ -0,0 +1,59 @@
+use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
+use fvm_shared::ActorID;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Privileges that can be granted to actors. Encoded as a single integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Minter = 0,
+    /// May burn any holder's tokens without an allowance.
+    Burner = 1,
+    Pauser = 2,
+    Blocklister = 3,
+    /// May change the token's name and symbol.
+    MetadataAdmin = 4,
+    /// May grant and revoke every role, including this one.
+    Admin = 5,
+    /// Manages the allowlist and switches allowlist-only mode.
+    AllowlistAdmin = 6,
+}
+
+impl Role {
+    pub const ALL: &'static [Role] = &[
+        Role::Minter,
+        Role::Burner,
+        Role::Pauser,
+        Role::Blocklister,
+        Role::MetadataAdmin,
+        Role::Admin,
+        Role::AllowlistAdmin,
+    ];
+
+    pub fn from_u8(num: u8) -> Option<Self> {
+        Role::ALL.iter().copied().find(|role| *role as u8 == num)
+    }
+}
+
+impl Serialize for Role {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for Role {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let num = u8::deserialize(deserializer)?;
+        Role::from_u8(num).ok_or_else(|| D::Error::custom(format!("unknown role {}", num)))
+    }
+}
+
+/// Emitted whenever a role is granted, revoked or renounced.
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, PartialEq)]
+pub struct RoleChanged {
+    pub role: Role,
+    pub account: ActorID,
+    /// The actor that made the change.
+    pub sender: ActorID,
+    pub granted: bool,
+}
@@ -0,0 +1,260 @@
+use crate::blockstore::Blockstore;
+use anyhow::{anyhow, Result};
+use cid::Cid;
+use fvm_ipld_encoding::RawBytes;
+use fvm_sdk as sdk;
+use fvm_shared::actor::builtin::Type;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::{ActorID, MethodNum};
+
+/// Everything the token logic needs from its execution environment.
+/// `FvmRuntime` forwards to the SDK syscalls; `MockRuntime` keeps it all in memory
+/// so `State` can be exercised by native unit tests.
+pub trait Runtime {
+    type Blockstore: fvm_ipld_blockstore::Blockstore;
+
+    /// ID of the actor that sent the current message.
+    fn caller(&self) -> ActorID;
+
+    /// ID of this actor.
+    fn receiver(&self) -> ActorID;
+
+    fn curr_epoch(&self) -> ChainEpoch;
+
+    /// FIL attached to the current message.
+    fn value_received(&self) -> TokenAmount;
+
+    /// This actor's FIL balance, including the value of the current message.
+    fn current_balance(&self) -> TokenAmount;
+
+    fn resolve_address(&self, address: &Address) -> Option<ActorID>;
+
+    /// Whether `id` is a built-in account actor, which has no receiver hook.
+    fn is_account(&self, id: ActorID) -> bool;
+
+    fn store(&self) -> &Self::Blockstore;
+
+    /// The actor's current state root.
+    fn root(&self) -> Result<Cid>;
+
+    fn set_root(&self, cid: &Cid) -> Result<()>;
+
+    fn send(
+        &self,
+        to: &Address,
+        method: MethodNum,
+        params: RawBytes,
+        value: TokenAmount,
+    ) -> Result<Receipt>;
+
+    /// Publishes an event. `topic` names the event and `data` is its CBOR encoding.
+    fn emit(&self, topic: &str, data: RawBytes);
+
+    /// Aborts the current message. Never returns.
+    fn abort(&self, code: u32, msg: &str) -> !;
+}
+
+/// Runtime backed by the FVM syscalls.
+#[derive(Default)]
+pub struct FvmRuntime {
+    blockstore: Blockstore,
+}
+
+impl Runtime for FvmRuntime {
+    type Blockstore = Blockstore;
+
+    fn caller(&self) -> ActorID {
+        sdk::message::caller()
+    }
+
+    fn receiver(&self) -> ActorID {
+        sdk::message::receiver()
+    }
+
+    fn curr_epoch(&self) -> ChainEpoch {
+        sdk::network::curr_epoch()
+    }
+
+    fn value_received(&self) -> TokenAmount {
+        sdk::message::value_received()
+    }
+
+    fn current_balance(&self) -> TokenAmount {
+        sdk::sself::current_balance()
+    }
+
+    fn resolve_address(&self, address: &Address) -> Option<ActorID> {
+        sdk::actor::resolve_address(address)
+    }
+
+    fn is_account(&self, id: ActorID) -> bool {
+        let code = sdk::actor::get_actor_code_cid(&Address::new_id(id));
+        matches!(
+            code.and_then(|code| sdk::actor::resolve_builtin_actor_type(&code)),
+            Some(Type::Account)
+        )
+    }
+
+    fn store(&self) -> &Blockstore {
+        &self.blockstore
+    }
+
+    fn root(&self) -> Result<Cid> {
+        sdk::sself::root().map_err(|e| anyhow!("failed to get root: {:?}", e))
+    }
+
+    fn set_root(&self, cid: &Cid) -> Result<()> {
+        sdk::sself::set_root(cid).map_err(|e| anyhow!("failed to set root: {:?}", e))
+    }
+
+    fn send(
+        &self,
+        to: &Address,
+        method: MethodNum,
+        params: RawBytes,
+        value: TokenAmount,
+    ) -> Result<Receipt> {
+        sdk::send::send(to, method, params, value)
+            .map_err(|e| anyhow!("send to {} failed with {:?}", to, e))
+    }
+
+    // The FVM has no event log yet, so events only reach the debug log; methods that
+    // emit them also return the record to the caller.
+    fn emit(&self, topic: &str, data: RawBytes) {
+        sdk::debug::log(format!("{} {}", topic, hex::encode(data.bytes())));
+    }
+
+    fn abort(&self, code: u32, msg: &str) -> ! {
+        sdk::vm::abort(code, Some(msg))
+    }
+}
+
+#[cfg(test)]
+pub use mock::MockRuntime;
+
+#[cfg(test)]
+mod mock {
+    use super::*;
+    use crate::state::empty_root;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::error::ExitCode;
+    use std::cell::{Cell, RefCell};
+    use std::collections::{HashMap, HashSet};
+
+    /// In-memory runtime for unit tests. Like a freshly created actor, it starts out with
+    /// an empty state root. Aborts panic with the exit code and message,
+    /// and sends and events are recorded instead of executed. Every send returns
+    /// `send_exit_code`.
+    pub struct MockRuntime {
+        pub caller: Cell<ActorID>,
+        pub receiver: ActorID,
+        pub epoch: Cell<ChainEpoch>,
+        pub value_received: RefCell<TokenAmount>,
+        pub balance: RefCell<TokenAmount>,
+        pub addresses: RefCell<HashMap<Address, ActorID>>,
+        pub accounts: RefCell<HashSet<ActorID>>,
+        pub send_exit_code: Cell<ExitCode>,
+        pub sent: RefCell<Vec<(Address, MethodNum, RawBytes, TokenAmount)>>,
+        pub events: RefCell<Vec<(String, RawBytes)>>,
+        store: MemoryBlockstore,
+        root: RefCell<Cid>,
+    }
+
+    impl MockRuntime {
+        pub fn new(receiver: ActorID) -> Self {
+            Self {
+                caller: Cell::new(0),
+                receiver,
+                epoch: Cell::new(0),
+                value_received: RefCell::new(TokenAmount::default()),
+                balance: RefCell::new(TokenAmount::default()),
+                addresses: RefCell::new(HashMap::new()),
+                accounts: RefCell::new(HashSet::new()),
+                send_exit_code: Cell::new(ExitCode::OK),
+                sent: RefCell::new(Vec::new()),
+                events: RefCell::new(Vec::new()),
+                store: MemoryBlockstore::new(),
+                root: RefCell::new(empty_root()),
+            }
+        }
+
+        pub fn set_caller(&self, caller: ActorID) {
+            self.caller.set(caller);
+        }
+    }
+
+    impl Runtime for MockRuntime {
+        type Blockstore = MemoryBlockstore;
+
+        fn caller(&self) -> ActorID {
+            self.caller.get()
+        }
+
+        fn receiver(&self) -> ActorID {
+            self.receiver
+        }
+
+        fn curr_epoch(&self) -> ChainEpoch {
+            self.epoch.get()
+        }
+
+        fn value_received(&self) -> TokenAmount {
+            self.value_received.borrow().clone()
+        }
+
+        fn current_balance(&self) -> TokenAmount {
+            self.balance.borrow().clone()
+        }
+
+        fn resolve_address(&self, address: &Address) -> Option<ActorID> {
+            if let Ok(id) = address.id() {
+                return Some(id);
+            }
+            self.addresses.borrow().get(address).copied()
+        }
+
+        fn is_account(&self, id: ActorID) -> bool {
+            self.accounts.borrow().contains(&id)
+        }
+
+        fn store(&self) -> &MemoryBlockstore {
+            &self.store
+        }
+
+        fn root(&self) -> Result<Cid> {
+            Ok(*self.root.borrow())
+        }
+
+        fn set_root(&self, cid: &Cid) -> Result<()> {
+            *self.root.borrow_mut() = *cid;
+            Ok(())
+        }
+
+        fn send(
+            &self,
+            to: &Address,
+            method: MethodNum,
+            params: RawBytes,
+            value: TokenAmount,
+        ) -> Result<Receipt> {
+            *self.balance.borrow_mut() -= &value;
+            self.sent.borrow_mut().push((*to, method, params, value));
+            Ok(Receipt {
+                exit_code: self.send_exit_code.get(),
+                return_data: RawBytes::default(),
+                gas_used: 0,
+            })
+        }
+
+        fn emit(&self, topic: &str, data: RawBytes) {
+            self.events.borrow_mut().push((topic.to_string(), data));
+        }
+
+        fn abort(&self, code: u32, msg: &str) -> ! {
+            panic!("abort with exit code {}: {}", code, msg)
+        }
+    }
+}
@@ -1,70 +1,304 @@
-use crate::blockstore::Blockstore;
-use crate::types::{MintLog, TransferLog};
+use crate::error::TokenError;
+use crate::role::{Role, RoleChanged};
+use crate::runtime::Runtime;
+use crate::types::{
+    ApproveLog, BurnFromReturn, BurnReturn, MintReturn, TransferFromReturn, TransferReturn,
+};
 use crate::uint256::Uint256;
-use cid::multihash::Code;
+use cid::multihash::{Code, MultihashDigest};
 use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
-use fvm_ipld_encoding::{to_vec, CborStore, RawBytes, DAG_CBOR};
-use fvm_sdk as sdk;
+use fvm_ipld_encoding::{CborStore, RawBytes, DAG_CBOR};
+use fvm_ipld_hamt::{BytesKey, Hamt};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigUint;
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::ActorID;
-use std::collections::HashMap;
-#[macro_use]
-use crate::abort;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Bit width of every HAMT in the token state.
+const HAMT_BIT_WIDTH: u32 = 5;
+
+type Map<'bs, BS, V> = Hamt<&'bs BS, V, BytesKey>;
+
+/// CID of an empty DAG-CBOR array: the state root of an actor that hasn't been constructed.
+pub fn empty_root() -> Cid {
+    Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&[0x80]))
+}
+
+fn actor_key(actor: ActorID) -> BytesKey {
+    BytesKey(actor.to_be_bytes().to_vec())
+}
+
+fn address_key(address: &Address) -> BytesKey {
+    BytesKey(address.to_bytes())
+}
+
+fn role_key(role: Role, actor: ActorID) -> BytesKey {
+    let mut key = vec![role as u8];
+    key.extend_from_slice(&actor.to_be_bytes());
+    BytesKey(key)
+}
+
+/// Stores an empty HAMT and returns its root.
+fn empty_map<BS, V>(store: &BS) -> Result<Cid, TokenError>
+where
+    BS: Blockstore,
+    V: Serialize + DeserializeOwned + PartialEq,
+{
+    Ok(Map::<BS, V>::new_with_bit_width(store, HAMT_BIT_WIDTH).flush()?)
+}
+
+fn load_map<'bs, BS, V>(store: &'bs BS, root: &Cid) -> Result<Map<'bs, BS, V>, TokenError>
+where
+    BS: Blockstore,
+    V: Serialize + DeserializeOwned + PartialEq,
+{
+    Ok(Map::load_with_bit_width(root, store, HAMT_BIT_WIDTH)?)
+}
+
+fn get_amount<BS: Blockstore>(
+    map: &Map<BS, Uint256>,
+    key: &BytesKey,
+) -> Result<Uint256, TokenError> {
+    Ok(map.get(key)?.cloned().unwrap_or_default())
+}
+
+/// Stores `amount` under `key`, deleting the entry once it drops to zero.
+fn set_amount<BS: Blockstore>(
+    map: &mut Map<BS, Uint256>,
+    key: BytesKey,
+    amount: Uint256,
+) -> Result<(), TokenError> {
+    if amount == Uint256::default() {
+        map.delete(&key)?;
+    } else {
+        map.set(key, amount)?;
+    }
+    Ok(())
+}
+
+/// Longest symbol the constructor accepts.
+const MAX_SYMBOL_LEN: usize = 12;
+
+/// Decimals of every token: deposits mint one base unit per attoFIL, so the token
+/// must share FIL's precision.
+const FIL_DECIMALS: u64 = 18;
+
 #[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, Default)]
 pub struct Token {
+    pub name: String,
     pub symbol: String,
+    pub granularity: u64,
     pub decimal: u64,
-    pub total_supply: Uint256,
+    /// Cap on the circulating supply; mints beyond it are rejected.
+    pub max_supply: Uint256,
 }
 
 #[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, Default)]
 pub struct State {
     pub token: Token,
-    pub balance_of: HashMap<ActorID, Uint256>,
-    pub allowance: HashMap<ActorID, HashMap<ActorID, Uint256>>,
+    /// Administrator of the token, made role admin and minter at construction.
+    /// `None` once ownership has been renounced.
+    pub owner: Option<ActorID>,
+    /// Nominee that may accept ownership, set by `propose_owner`.
+    pub pending_owner: Option<ActorID>,
+    /// While set, tokens can't be minted, moved, burned or approved.
+    pub paused: bool,
+    /// Epoch `paused` last changed at.
+    pub paused_at: ChainEpoch,
+    /// Root of a HAMT holding an entry for every (role, actor) pair that was granted.
+    pub roles: Cid,
+    /// Root of a HAMT holding an entry for every frozen actor.
+    pub frozen: Cid,
+    /// While set, only allowlisted actors can send or receive tokens.
+    pub allowlist_only: bool,
+    /// Root of a HAMT holding an entry for every allowlisted actor.
+    pub allowlist: Cid,
+    /// Tokens currently in existence: everything minted minus everything burned.
+    pub circulating_supply: Uint256,
+    /// Root of a HAMT from holder ID to balance. Zero balances are not stored.
+    pub balance_of: Cid,
+    /// Root of a HAMT from owner ID to the root of that owner's spender HAMT,
+    /// which maps spender ID to `AllowanceEntry`. Empty spender HAMTs are removed.
+    pub allowance: Cid,
+    /// Root of a HAMT holding balances credited to key addresses that had no actor yet,
+    /// keyed by address. Moved to `balance_of` the first time the address resolves to an ID.
+    pub pending_balances: Cid,
+}
+
+/// An allowance as stored in an owner's spender HAMT.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq)]
+pub struct AllowanceEntry {
+    pub amount: Uint256,
+    /// Last epoch the allowance can be spent in. `None` never expires.
+    pub expiry: Option<ChainEpoch>,
+}
+
+impl AllowanceEntry {
+    fn expired(&self, epoch: ChainEpoch) -> bool {
+        matches!(self.expiry, Some(expiry) if expiry < epoch)
+    }
+}
+
+/// Allowance that spending never decrements, for spenders trusted with the whole balance.
+pub fn unlimited_allowance() -> Uint256 {
+    Uint256::max_value()
+}
+
+/// The receiving side of a mint or transfer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recipient {
+    /// An existing actor.
+    Id(ActorID),
+    /// A key address with no actor yet; its tokens are held as a pending balance.
+    Pending(Address),
 }
 
 /// We should probably have a derive macro to mark an object as a state object,
 /// and have load and save methods automatically generated for them as part of a
 /// StateObject trait (i.e. impl StateObject for State).
 impl State {
-    pub fn load() -> Self {
+    /// Whether the actor has been constructed, i.e. its state root is no longer empty.
+    /// A root that can't be loaded as a token state is an error, not a fresh actor.
+    pub fn exists<RT: Runtime>(rt: &RT) -> Result<bool, TokenError> {
+        let root = rt
+            .root()
+            .map_err(|err| TokenError::IllegalState(err.to_string()))?;
+        if root == empty_root() {
+            return Ok(false);
+        }
+        match rt.store().get_cbor::<Self>(&root) {
+            Ok(Some(_)) => Ok(true),
+            Ok(None) => Err(TokenError::IllegalState(format!(
+                "state root {} not found",
+                root
+            ))),
+            Err(err) => Err(TokenError::IllegalState(format!(
+                "failed to get state: {}",
+                err
+            ))),
+        }
+    }
+
+    pub fn load<RT: Runtime>(rt: &RT) -> Result<Self, TokenError> {
         // First, load the current state root.
-        let root = match sdk::sself::root() {
-            Ok(root) => root,
-            Err(err) => abort!(USR_ILLEGAL_STATE, "failed to get root: {:?}", err),
-        };
+        let root = rt
+            .root()
+            .map_err(|err| TokenError::IllegalState(format!("failed to get root: {}", err)))?;
 
         // Load the actor state from the state tree.
-        match Blockstore.get_cbor::<Self>(&root) {
-            Ok(Some(state)) => state,
-            Ok(None) => abort!(USR_ILLEGAL_STATE, "state does not exist"),
-            Err(err) => abort!(USR_ILLEGAL_STATE, "failed to get state: {}", err),
+        match rt.store().get_cbor::<Self>(&root) {
+            Ok(Some(state)) => Ok(state),
+            Ok(None) => Err(TokenError::IllegalState("state does not exist".to_string())),
+            Err(err) => Err(TokenError::IllegalState(format!(
+                "failed to get state: {}",
+                err
+            ))),
         }
     }
 
-    pub fn save(&self) -> Cid {
-        let serialized = match to_vec(self) {
-            Ok(s) => s,
-            Err(err) => abort!(USR_SERIALIZATION, "failed to serialize state: {:?}", err),
-        };
-        let serialized = serialized.as_slice();
-        let cid = match sdk::ipld::put(Code::Blake2b256.into(), 32, DAG_CBOR, &serialized) {
-            Ok(cid) => cid,
-            Err(err) => abort!(USR_SERIALIZATION, "failed to store initial state: {:}", err),
-        };
-        if let Err(err) = sdk::sself::set_root(&cid) {
-            abort!(USR_ILLEGAL_STATE, "failed to set root ciid: {:}", err);
-        }
-        // abort!(USR_ILLEGAL_STATE, "failed to set root ciid:");
-        cid
+    pub fn save<RT: Runtime>(&self, rt: &RT) -> Result<Cid, TokenError> {
+        let cid = rt
+            .store()
+            .put_cbor(self, Code::Blake2b256)
+            .map_err(|err| TokenError::IllegalState(format!("failed to store state: {}", err)))?;
+        rt.set_root(&cid)
+            .map_err(|err| TokenError::IllegalState(format!("failed to set root cid: {}", err)))?;
+        Ok(cid)
     }
 
-    pub fn constructor(&mut self, token: Token) -> Option<RawBytes> {
+    pub fn constructor<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        token: Token,
+        owner: ActorID,
+        allowlist_only: bool,
+        allocations: Vec<(Recipient, Uint256)>,
+    ) -> Result<Option<RawBytes>, TokenError> {
+        Self::validate_token(&token)?;
         self.token = token;
-        self.balance_of = HashMap::new();
-        self.allowance = HashMap::new();
-        None
+        self.owner = Some(owner);
+        self.pending_owner = None;
+        self.paused = false;
+        self.paused_at = rt.curr_epoch();
+        self.roles = empty_map::<_, bool>(rt.store())?;
+        self.frozen = empty_map::<_, bool>(rt.store())?;
+        self.allowlist_only = allowlist_only;
+        self.allowlist = empty_map::<_, bool>(rt.store())?;
+        self.circulating_supply = Uint256::default();
+        self.balance_of = empty_map::<_, Uint256>(rt.store())?;
+        self.allowance = empty_map::<_, Cid>(rt.store())?;
+        self.pending_balances = empty_map::<_, Uint256>(rt.store())?;
+        self.set_owner_roles(rt, owner, true)?;
+        if allowlist_only {
+            // Nobody could be allowlisted yet, so the owner and allocation recipients are.
+            self.set_allowlisted(rt, owner, true)?;
+            for (to, _) in &allocations {
+                if let Recipient::Id(actor) = to {
+                    self.set_allowlisted(rt, *actor, true)?;
+                }
+            }
+        }
+        // Like any mint, the allocations must fit under the cap and be backed by FIL,
+        // here the value sent along with the actor creation.
+        for (to, amount) in allocations {
+            self.issue(rt, to, amount)?;
+        }
+        Ok(None)
+    }
+
+    fn validate_token(token: &Token) -> Result<(), TokenError> {
+        Self::validate_metadata(&token.name, &token.symbol)?;
+        if token.decimal != FIL_DECIMALS {
+            return Err(TokenError::InvalidConfig(
+                "decimals must be 18 to match FIL",
+            ));
+        }
+        if token.max_supply == Uint256::default() {
+            return Err(TokenError::InvalidConfig("max supply must be positive"));
+        }
+        if token.max_supply.big_uint.bits() > 256 {
+            return Err(TokenError::InvalidConfig("max supply must fit in 256 bits"));
+        }
+        Ok(())
+    }
+
+    fn validate_metadata(name: &str, symbol: &str) -> Result<(), TokenError> {
+        if name.is_empty() {
+            return Err(TokenError::InvalidConfig("name is empty"));
+        }
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_LEN {
+            return Err(TokenError::InvalidConfig(
+                "symbol must be 1 to 12 characters",
+            ));
+        }
+        if !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(TokenError::InvalidConfig(
+                "symbol must be ASCII letters and digits",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Renames the token. Only metadata admins may call this.
+    pub fn set_metadata<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        name: String,
+        symbol: String,
+    ) -> Result<(), TokenError> {
+        self.require_role(rt, Role::MetadataAdmin, "change the token metadata")?;
+        Self::validate_metadata(&name, &symbol)?;
+        self.token.name = name;
+        self.token.symbol = symbol;
+        Ok(())
+    }
+
+    pub fn name(&self) -> String {
+        self.token.name.clone()
     }
 
     pub fn symbol(&self) -> String {
@@ -75,127 +309,888 @@ impl State {
         self.token.decimal
     }
 
+    pub fn granularity(&self) -> u64 {
+        self.token.granularity
+    }
+
+    /// The circulating supply.
     pub fn total_supply(&self) -> Uint256 {
-        self.token.total_supply.clone()
+        self.circulating_supply.clone()
     }
 
-    pub fn mint(&mut self, actor: ActorID, amount: Uint256) -> MintLog {
-        let mut default_balance: Uint256 = Default::default();
-        match self.balance_of.get_mut(&actor) {
-            None => {
-                self.balance_of.insert(actor, amount.clone());
-            }
-            Some(balance) => {
-                *balance = balance.clone() + amount.clone();
-            }
+    pub fn max_supply(&self) -> Uint256 {
+        self.token.max_supply.clone()
+    }
+
+    pub fn owner(&self) -> Option<ActorID> {
+        self.owner
+    }
+
+    pub fn pending_owner(&self) -> Option<ActorID> {
+        self.pending_owner
+    }
+
+    pub fn has_role<RT: Runtime>(
+        &self,
+        rt: &RT,
+        role: Role,
+        actor: ActorID,
+    ) -> Result<bool, TokenError> {
+        let roles = load_map::<_, bool>(rt.store(), &self.roles)?;
+        Ok(roles.contains_key(&role_key(role, actor))?)
+    }
+
+    /// Guard for privileged methods: returns the caller if it holds `role`.
+    pub fn require_role<RT: Runtime>(
+        &self,
+        rt: &RT,
+        role: Role,
+        action: &'static str,
+    ) -> Result<ActorID, TokenError> {
+        let caller = rt.caller();
+        if !self.has_role(rt, role, caller)? {
+            return Err(TokenError::Unauthorized { caller, action });
+        }
+        Ok(caller)
+    }
+
+    /// Grants or revokes `role` for `account`. If anything changed, emits a `RoleChanged`
+    /// event and returns it so callers can pass it back in the method's return value.
+    fn set_role<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        role: Role,
+        account: ActorID,
+        granted: bool,
+    ) -> Result<Option<RoleChanged>, TokenError> {
+        let mut roles = load_map::<_, bool>(rt.store(), &self.roles)?;
+        let key = role_key(role, account);
+        let changed = if granted {
+            roles.set_if_absent(key, true)?
+        } else {
+            roles.delete(&key)?.is_some()
         };
+        if !changed {
+            return Ok(None);
+        }
+        self.roles = roles.flush()?;
+        let event = RoleChanged {
+            role,
+            account,
+            sender: rt.caller(),
+            granted,
+        };
+        let data =
+            RawBytes::serialize(&event).map_err(|err| TokenError::IllegalState(err.to_string()))?;
+        rt.emit("RoleChanged", data);
+        Ok(Some(event))
+    }
 
-        MintLog::new(actor, amount)
+    /// Gives `account` the `role`. Only role admins may call this.
+    /// Returns the `RoleChanged` record, or `None` if `account` already had the role.
+    pub fn grant_role<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        role: Role,
+        account: ActorID,
+    ) -> Result<Option<RoleChanged>, TokenError> {
+        self.require_role(rt, Role::Admin, "grant roles")?;
+        self.set_role(rt, role, account, true)
     }
 
-    pub fn balance_of(&self, actor: ActorID) -> Uint256 {
-        let default_balance = Uint256::default();
-        let balacnce = self.balance_of.get(&actor).unwrap_or(&default_balance);
-        balacnce.clone()
+    /// Takes the `role` away from `account`. Only role admins may call this.
+    /// Returns the `RoleChanged` record, or `None` if `account` didn't have the role.
+    pub fn revoke_role<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        role: Role,
+        account: ActorID,
+    ) -> Result<Option<RoleChanged>, TokenError> {
+        self.require_role(rt, Role::Admin, "revoke roles")?;
+        self.set_role(rt, role, account, false)
     }
 
-    pub fn transfer(&mut self, to: ActorID, amount: Uint256) -> TransferLog {
-        unsafe {
-            let from = sdk::sys::message::caller().unwrap();
+    /// Drops one of the caller's own roles.
+    pub fn renounce_role<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        role: Role,
+    ) -> Result<Option<RoleChanged>, TokenError> {
+        self.set_role(rt, role, rt.caller(), false)
+    }
 
-            let mut from_default_balance = Uint256::default();
+    /// Rejects callers other than the owner.
+    fn check_owner<RT: Runtime>(&self, rt: &RT, action: &'static str) -> Result<(), TokenError> {
+        let caller = rt.caller();
+        if self.owner != Some(caller) {
+            return Err(TokenError::Unauthorized { caller, action });
+        }
+        Ok(())
+    }
 
-            let from_balance = self.balance_of.get_mut(&from);
-            match from_balance {
-                None => {
-                    abort!(SYS_ASSERTION_FAILED, "Insufficient Balance");
-                }
-                Some(from_balance) => {
-                    if *from_balance < amount {
-                        abort!(SYS_ASSERTION_FAILED, "Insufficient Balance")
-                    }
-                    *from_balance = from_balance.clone() - amount.clone();
-                }
-            }
+    /// Nominates `nominee` as the next owner, replacing any earlier proposal.
+    /// Ownership only moves once the nominee accepts.
+    pub fn propose_owner<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        nominee: ActorID,
+    ) -> Result<(), TokenError> {
+        self.check_owner(rt, "propose a new owner")?;
+        self.pending_owner = Some(nominee);
+        Ok(())
+    }
 
-            match self.balance_of.get_mut(&to) {
-                None => {
-                    self.balance_of.insert(to, amount.clone());
-                }
-                Some(to_balance) => {
-                    *to_balance = to_balance.clone() + amount.clone();
-                }
-            }
+    /// Grants or revokes the roles that come with ownership.
+    fn set_owner_roles<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        account: ActorID,
+        granted: bool,
+    ) -> Result<(), TokenError> {
+        self.set_role(rt, Role::Admin, account, granted)?;
+        self.set_role(rt, Role::Minter, account, granted)?;
+        Ok(())
+    }
 
-            TransferLog::new(from, to, amount)
+    /// Makes the caller the owner, moving the owner's roles over from the previous one.
+    /// Only the pending nominee may call this.
+    pub fn accept_ownership<RT: Runtime>(&mut self, rt: &RT) -> Result<(), TokenError> {
+        let caller = rt.caller();
+        if self.pending_owner != Some(caller) {
+            return Err(TokenError::Unauthorized {
+                caller,
+                action: "accept ownership",
+            });
+        }
+        if let Some(owner) = self.owner {
+            self.set_owner_roles(rt, owner, false)?;
         }
+        self.set_owner_roles(rt, caller, true)?;
+        self.owner = Some(caller);
+        self.pending_owner = None;
+        Ok(())
+    }
+
+    /// Withdraws the pending ownership proposal, if any.
+    pub fn cancel_ownership_proposal<RT: Runtime>(&mut self, rt: &RT) -> Result<(), TokenError> {
+        self.check_owner(rt, "cancel the ownership proposal")?;
+        self.pending_owner = None;
+        Ok(())
     }
 
-    pub fn approve(&mut self, to: ActorID, amount: Uint256) -> Option<RawBytes> {
-        unsafe {
-            let from = sdk::sys::message::caller().unwrap();
+    /// Gives up ownership and the owner's roles for good: owner-only methods can never
+    /// be called again.
+    pub fn renounce_ownership<RT: Runtime>(&mut self, rt: &RT) -> Result<(), TokenError> {
+        self.check_owner(rt, "renounce ownership")?;
+        self.set_owner_roles(rt, rt.caller(), false)?;
+        self.owner = None;
+        self.pending_owner = None;
+        Ok(())
+    }
 
-            let default_balance = Uint256::default();
-            let from_balance = self
-                .balance_of
-                .get(&from)
-                .unwrap_or(&default_balance)
-                .clone();
-            if from_balance < amount {
-                abort!(SYS_ASSERTION_FAILED, "Insufficient Balance")
-            }
-            if let None = self.allowance.get_mut(&from) {
-                self.allowance.insert(from, HashMap::new());
+    /// Lets `minter` mint. Shorthand for granting `Role::Minter`, so only role admins
+    /// may call this; adding an existing minter is a no-op.
+    pub fn add_minter<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        minter: ActorID,
+    ) -> Result<Option<RoleChanged>, TokenError> {
+        self.require_role(rt, Role::Admin, "add minters")?;
+        self.set_role(rt, Role::Minter, minter, true)
+    }
+
+    /// Stops `minter` from minting. Only role admins may call this.
+    pub fn remove_minter<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        minter: ActorID,
+    ) -> Result<Option<RoleChanged>, TokenError> {
+        self.require_role(rt, Role::Admin, "remove minters")?;
+        self.set_role(rt, Role::Minter, minter, false)
+    }
+
+    /// Stops minting, transfers, burns and approvals. Only pausers may call this.
+    /// Lowering or revoking allowances keeps working.
+    pub fn pause<RT: Runtime>(&mut self, rt: &RT) -> Result<(), TokenError> {
+        self.require_role(rt, Role::Pauser, "pause")?;
+        self.set_paused(rt, true);
+        Ok(())
+    }
+
+    pub fn unpause<RT: Runtime>(&mut self, rt: &RT) -> Result<(), TokenError> {
+        self.require_role(rt, Role::Pauser, "unpause")?;
+        self.set_paused(rt, false);
+        Ok(())
+    }
+
+    fn set_paused<RT: Runtime>(&mut self, rt: &RT, paused: bool) {
+        if self.paused != paused {
+            self.paused = paused;
+            self.paused_at = rt.curr_epoch();
+        }
+    }
+
+    /// Whether the token is paused, and the epoch that last changed at.
+    pub fn paused(&self) -> (bool, ChainEpoch) {
+        (self.paused, self.paused_at)
+    }
+
+    fn check_not_paused(&self) -> Result<(), TokenError> {
+        if self.paused {
+            return Err(TokenError::Paused);
+        }
+        Ok(())
+    }
+
+    /// Stops `account` from sending, receiving or approving tokens, and from spending
+    /// allowances. Only blocklisters may call this.
+    pub fn freeze<RT: Runtime>(&mut self, rt: &RT, account: ActorID) -> Result<(), TokenError> {
+        self.require_role(rt, Role::Blocklister, "freeze accounts")?;
+        let mut frozen = load_map::<_, bool>(rt.store(), &self.frozen)?;
+        frozen.set(actor_key(account), true)?;
+        self.frozen = frozen.flush()?;
+        Ok(())
+    }
+
+    pub fn unfreeze<RT: Runtime>(&mut self, rt: &RT, account: ActorID) -> Result<(), TokenError> {
+        self.require_role(rt, Role::Blocklister, "unfreeze accounts")?;
+        let mut frozen = load_map::<_, bool>(rt.store(), &self.frozen)?;
+        frozen.delete(&actor_key(account))?;
+        self.frozen = frozen.flush()?;
+        Ok(())
+    }
+
+    pub fn is_frozen<RT: Runtime>(&self, rt: &RT, account: ActorID) -> Result<bool, TokenError> {
+        let frozen = load_map::<_, bool>(rt.store(), &self.frozen)?;
+        Ok(frozen.contains_key(&actor_key(account))?)
+    }
+
+    /// Every frozen actor, in no particular order.
+    pub fn frozen_accounts<RT: Runtime>(&self, rt: &RT) -> Result<Vec<ActorID>, TokenError> {
+        let frozen = load_map::<_, bool>(rt.store(), &self.frozen)?;
+        let mut accounts = Vec::new();
+        frozen.for_each(|key, _| {
+            let bytes = key.0.as_slice().try_into()?;
+            accounts.push(ActorID::from_be_bytes(bytes));
+            Ok(())
+        })?;
+        Ok(accounts)
+    }
+
+    /// Turns allowlist-only mode on or off. Only allowlist admins may call this.
+    pub fn set_allowlist_only<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        enabled: bool,
+    ) -> Result<(), TokenError> {
+        self.require_role(rt, Role::AllowlistAdmin, "switch allowlist-only mode")?;
+        self.allowlist_only = enabled;
+        Ok(())
+    }
+
+    pub fn allowlist_only(&self) -> bool {
+        self.allowlist_only
+    }
+
+    /// Adds `account` to the allowlist. Only allowlist admins may call this.
+    pub fn add_to_allowlist<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        account: ActorID,
+    ) -> Result<(), TokenError> {
+        self.require_role(rt, Role::AllowlistAdmin, "add to the allowlist")?;
+        self.set_allowlisted(rt, account, true)
+    }
+
+    pub fn remove_from_allowlist<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        account: ActorID,
+    ) -> Result<(), TokenError> {
+        self.require_role(rt, Role::AllowlistAdmin, "remove from the allowlist")?;
+        self.set_allowlisted(rt, account, false)
+    }
+
+    pub fn is_allowlisted<RT: Runtime>(
+        &self,
+        rt: &RT,
+        account: ActorID,
+    ) -> Result<bool, TokenError> {
+        let allowlist = load_map::<_, bool>(rt.store(), &self.allowlist)?;
+        Ok(allowlist.contains_key(&actor_key(account))?)
+    }
+
+    fn set_allowlisted<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        account: ActorID,
+        allowed: bool,
+    ) -> Result<(), TokenError> {
+        let mut allowlist = load_map::<_, bool>(rt.store(), &self.allowlist)?;
+        if allowed {
+            allowlist.set(actor_key(account), true)?;
+        } else {
+            allowlist.delete(&actor_key(account))?;
+        }
+        self.allowlist = allowlist.flush()?;
+        Ok(())
+    }
+
+    /// In allowlist-only mode, rejects `account` unless it's allowlisted.
+    /// Pending recipients have no actor to allowlist, so they're always rejected.
+    fn check_allowlisted<RT: Runtime>(
+        &self,
+        rt: &RT,
+        account: &Recipient,
+    ) -> Result<(), TokenError> {
+        if !self.allowlist_only {
+            return Ok(());
+        }
+        let address = match account {
+            Recipient::Id(actor) if self.is_allowlisted(rt, *actor)? => return Ok(()),
+            Recipient::Id(actor) => Address::new_id(*actor),
+            Recipient::Pending(address) => *address,
+        };
+        Err(TokenError::NotAllowlisted(address))
+    }
+
+    fn check_not_frozen<RT: Runtime>(&self, rt: &RT, account: ActorID) -> Result<(), TokenError> {
+        if self.is_frozen(rt, account)? {
+            return Err(TokenError::Frozen(account));
+        }
+        Ok(())
+    }
+
+    /// Rejects amounts that aren't a multiple of the token's granularity.
+    fn check_granularity(&self, amount: &Uint256) -> Result<(), TokenError> {
+        let granularity = self.token.granularity.max(1);
+        if &amount.big_uint % granularity != BigUint::from(0u64) {
+            return Err(TokenError::InvalidAmount {
+                amount: amount.clone(),
+                reason: "not a multiple of the token granularity",
+            });
+        }
+        Ok(())
+    }
+
+    pub fn mint<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        to: Recipient,
+        amount: Uint256,
+    ) -> Result<MintReturn, TokenError> {
+        self.require_role(rt, Role::Minter, "mint")?;
+        // A mint is a deposit made for someone else: it must attach exactly the FIL backing
+        // it, so FIL left over by burns can't be minted against.
+        if rt.value_received() != amount.to_token_amount() {
+            return Err(TokenError::InvalidAmount {
+                amount,
+                reason: "mints must attach exactly the minted amount of FIL",
+            });
+        }
+        self.issue(rt, to, amount)
+    }
+
+    /// Wraps the FIL attached to the message, minting the same amount of tokens to the caller.
+    /// Returns the amount minted along with the usual mint receipt.
+    pub fn deposit<RT: Runtime>(&mut self, rt: &RT) -> Result<(Uint256, MintReturn), TokenError> {
+        let value = rt.value_received();
+        let amount = Uint256::from_token_amount(&value).ok_or(TokenError::Overflow)?;
+        if amount == Uint256::default() {
+            return Err(TokenError::InvalidAmount {
+                amount,
+                reason: "nothing was deposited",
+            });
+        }
+        let res = self.issue(rt, Recipient::Id(rt.caller()), amount.clone())?;
+        Ok((amount, res))
+    }
+
+    /// Burns `amount` of the caller's tokens. The caller must then be sent the same amount
+    /// of FIL; since that lowers reserves and supply alike, the reserves only need to cover
+    /// the supply beforehand.
+    pub fn withdraw<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        amount: Uint256,
+    ) -> Result<BurnReturn, TokenError> {
+        self.check_not_paused()?;
+        self.check_granularity(&amount)?;
+        self.check_reserves(rt, &self.circulating_supply)?;
+        let owner = rt.caller();
+        self.debit(rt, owner, amount.clone())?;
+        self.circulating_supply = self.circulating_supply.clone() - amount;
+        Ok(BurnReturn {
+            balance: self.balance_of(rt, owner)?,
+        })
+    }
+
+    /// Checks that the actor's FIL balance covers a circulating supply of `supply`.
+    fn check_reserves<RT: Runtime>(&self, rt: &RT, supply: &Uint256) -> Result<(), TokenError> {
+        let reserves = rt.current_balance();
+        if reserves < supply.to_token_amount() {
+            return Err(TokenError::InsufficientReserves {
+                reserves,
+                supply: supply.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Creates `amount` new tokens for `to`, within the supply cap and the FIL reserves.
+    fn issue<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        to: Recipient,
+        amount: Uint256,
+    ) -> Result<MintReturn, TokenError> {
+        self.check_not_paused()?;
+        self.check_granularity(&amount)?;
+        let supply = self
+            .circulating_supply
+            .checked_add(&amount)
+            .ok_or(TokenError::Overflow)?;
+        if supply > self.token.max_supply {
+            return Err(TokenError::SupplyCapExceeded {
+                max_supply: self.token.max_supply.clone(),
+                supply: self.circulating_supply.clone(),
+                amount,
+            });
+        }
+        self.check_reserves(rt, &supply)?;
+        self.credit(rt, &to, amount)?;
+        self.circulating_supply = supply;
+
+        Ok(MintReturn {
+            balance: self.recipient_balance(rt, &to)?,
+            supply: self.total_supply(),
+            recipient_data: RawBytes::default(),
+        })
+    }
+
+    pub fn balance_of<RT: Runtime>(&self, rt: &RT, actor: ActorID) -> Result<Uint256, TokenError> {
+        let balances = load_map(rt.store(), &self.balance_of)?;
+        get_amount(&balances, &actor_key(actor))
+    }
+
+    pub fn pending_balance<RT: Runtime>(
+        &self,
+        rt: &RT,
+        address: &Address,
+    ) -> Result<Uint256, TokenError> {
+        let pending = load_map(rt.store(), &self.pending_balances)?;
+        get_amount(&pending, &address_key(address))
+    }
+
+    pub fn recipient_balance<RT: Runtime>(
+        &self,
+        rt: &RT,
+        to: &Recipient,
+    ) -> Result<Uint256, TokenError> {
+        match to {
+            Recipient::Id(actor) => self.balance_of(rt, *actor),
+            Recipient::Pending(address) => self.pending_balance(rt, address),
+        }
+    }
+
+    fn credit<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        to: &Recipient,
+        amount: Uint256,
+    ) -> Result<(), TokenError> {
+        if let Recipient::Id(actor) = to {
+            self.check_not_frozen(rt, *actor)?;
+        }
+        self.check_allowlisted(rt, to)?;
+        let (root, key) = match to {
+            Recipient::Id(actor) => (&mut self.balance_of, actor_key(*actor)),
+            Recipient::Pending(address) => (&mut self.pending_balances, address_key(address)),
+        };
+        let mut map = load_map(rt.store(), root)?;
+        let balance = get_amount(&map, &key)?
+            .checked_add(&amount)
+            .ok_or(TokenError::Overflow)?;
+        set_amount(&mut map, key, balance)?;
+        *root = map.flush()?;
+        Ok(())
+    }
+
+    fn debit<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        owner: ActorID,
+        amount: Uint256,
+    ) -> Result<(), TokenError> {
+        self.check_not_frozen(rt, owner)?;
+        self.check_allowlisted(rt, &Recipient::Id(owner))?;
+        self.take_balance(rt, owner, amount)
+    }
+
+    /// Takes `amount` off `owner`'s balance, without the frozen and allowlist checks.
+    fn take_balance<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        owner: ActorID,
+        amount: Uint256,
+    ) -> Result<(), TokenError> {
+        let mut balances = load_map(rt.store(), &self.balance_of)?;
+        let balance = get_amount(&balances, &actor_key(owner))?;
+        if balance < amount {
+            return Err(TokenError::InsufficientBalance {
+                owner,
+                balance,
+                amount,
+            });
+        }
+        set_amount(&mut balances, actor_key(owner), balance - amount)?;
+        self.balance_of = balances.flush()?;
+        Ok(())
+    }
+
+    /// Moves any pending balance held for `address` to the actor it now resolves to.
+    pub fn migrate_pending<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        address: &Address,
+        actor: ActorID,
+    ) -> Result<(), TokenError> {
+        let mut pending = load_map::<_, Uint256>(rt.store(), &self.pending_balances)?;
+        if let Some((_, amount)) = pending.delete(&address_key(address))? {
+            self.pending_balances = pending.flush()?;
+            self.credit(rt, &Recipient::Id(actor), amount)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the balance pending under the caller's key `address` onto the caller, so a
+    /// wallet can spend tokens sent to it before its actor existed. Returns the caller's
+    /// new balance.
+    pub fn claim<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        address: &Address,
+    ) -> Result<Uint256, TokenError> {
+        let caller = rt.caller();
+        if rt.resolve_address(address) != Some(caller) {
+            return Err(TokenError::Unauthorized {
+                caller,
+                action: "claim the pending balance",
+            });
+        }
+        self.migrate_pending(rt, address, caller)?;
+        self.balance_of(rt, caller)
+    }
+
+    pub fn transfer<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        to: Recipient,
+        amount: Uint256,
+    ) -> Result<TransferReturn, TokenError> {
+        self.check_not_paused()?;
+        self.check_granularity(&amount)?;
+        let from = rt.caller();
+
+        self.debit(rt, from, amount.clone())?;
+        self.credit(rt, &to, amount)?;
+
+        Ok(TransferReturn {
+            from_balance: self.balance_of(rt, from)?,
+            to_balance: self.recipient_balance(rt, &to)?,
+            recipient_data: RawBytes::default(),
+        })
+    }
+
+    pub fn approve<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        to: ActorID,
+        amount: Uint256,
+        expiry: Option<ChainEpoch>,
+    ) -> Result<ApproveLog, TokenError> {
+        self.check_not_paused()?;
+        // Nothing may exceed the unlimited allowance, which is never decremented.
+        if amount > unlimited_allowance() {
+            return Err(TokenError::Overflow);
+        }
+        if let Some(expiry) = expiry {
+            let epoch = rt.curr_epoch();
+            if expiry < epoch {
+                return Err(TokenError::InvalidExpiry { expiry, epoch });
             }
-            self.allowance.get_mut(&from).unwrap().insert(to, amount);
-            None
         }
+        // The owner's balance doesn't matter: spends are checked against it when they happen.
+        let from = rt.caller();
+        self.check_not_frozen(rt, from)?;
+        self.check_not_frozen(rt, to)?;
+        self.set_allowance(rt, from, to, amount.clone(), expiry)?;
+        Ok(ApproveLog::new(from, to, amount, expiry))
     }
 
-    pub fn allowance(&self, from: &ActorID, to: &ActorID) -> Uint256 {
-        match self.allowance.get(from) {
-            None => return Uint256::default(),
-            Some(allow) => match allow.get(to) {
-                None => return Uint256::default(),
-                Some(balance) => return balance.clone(),
-            },
+    /// Like `approve`, but only if `operator`'s current allowance is still `expected`.
+    /// Avoids the race where a spender uses both the old and the new allowance.
+    pub fn approve_if<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        operator: ActorID,
+        expected: Uint256,
+        amount: Uint256,
+        expiry: Option<ChainEpoch>,
+    ) -> Result<ApproveLog, TokenError> {
+        let actual = self.allowance(rt, &rt.caller(), &operator)?;
+        if actual != expected {
+            return Err(TokenError::AllowanceMismatch { expected, actual });
         }
+        self.approve(rt, operator, amount, expiry)
     }
 
-    pub fn transfer_from(&mut self, from: ActorID, to: ActorID, amount: Uint256) -> TransferLog {
-        unsafe {
-            match self.allowance.get_mut(&from) {
-                None => {
-                    abort!(SYS_ASSERTION_FAILED, "Insufficient Balance");
-                }
-                Some(allowance) => {
-                    let mut allowance_default_balance: Uint256 = Default::default();
-                    match allowance.get_mut(&to) {
-                        None => {
-                            abort!(SYS_ASSERTION_FAILED, "Insufficient Balance");
-                        }
-                        Some(value) => {
-                            if *value < amount {
-                                abort!(SYS_ASSERTION_FAILED, "Insufficient Balance")
-                            }
-                            let from_balance = self.balance_of.get_mut(&from).unwrap();
-                            *from_balance = from_balance.clone() - amount.clone();
-
-                            match self.balance_of.get_mut(&to) {
-                                None => {
-                                    self.balance_of.insert(to, amount.clone());
-                                }
-                                Some(to_balance) => {
-                                    *to_balance = to_balance.clone() + amount.clone();
-                                    *value = value.clone() - amount.clone();
-                                }
-                            }
-                        }
-                    }
+    /// Zero once the allowance has expired.
+    pub fn allowance<RT: Runtime>(
+        &self,
+        rt: &RT,
+        from: &ActorID,
+        to: &ActorID,
+    ) -> Result<Uint256, TokenError> {
+        Ok(self
+            .allowance_entry(rt, *from, *to)?
+            .map(|entry| entry.amount)
+            .unwrap_or_default())
+    }
+
+    /// The allowance `operator` may spend on behalf of `owner`, unless it's missing or expired.
+    pub fn allowance_entry<RT: Runtime>(
+        &self,
+        rt: &RT,
+        owner: ActorID,
+        operator: ActorID,
+    ) -> Result<Option<AllowanceEntry>, TokenError> {
+        let owners = load_map::<_, Cid>(rt.store(), &self.allowance)?;
+        let spenders = match owners.get(&actor_key(owner))? {
+            Some(spenders) => load_map::<_, AllowanceEntry>(rt.store(), spenders)?,
+            None => return Ok(None),
+        };
+        let entry = spenders.get(&actor_key(operator))?;
+        Ok(entry
+            .filter(|entry| !entry.expired(rt.curr_epoch()))
+            .cloned())
+    }
+
+    /// Moves `amount` of `owner`'s tokens to `to`, spending the caller's allowance.
+    pub fn transfer_from<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        owner: ActorID,
+        to: Recipient,
+        amount: Uint256,
+    ) -> Result<TransferFromReturn, TokenError> {
+        self.check_not_paused()?;
+        self.check_granularity(&amount)?;
+        let allowance = self.spend_allowance(rt, owner, amount.clone())?;
+        self.debit(rt, owner, amount.clone())?;
+        self.credit(rt, &to, amount)?;
+
+        Ok(TransferFromReturn {
+            from_balance: self.balance_of(rt, owner)?,
+            to_balance: self.recipient_balance(rt, &to)?,
+            allowance,
+            recipient_data: RawBytes::default(),
+        })
+    }
+
+    /// Takes `amount` off the caller's allowance from `owner` and returns what's left.
+    /// Unlimited allowances are left as they are.
+    fn spend_allowance<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        owner: ActorID,
+        amount: Uint256,
+    ) -> Result<Uint256, TokenError> {
+        let operator = rt.caller();
+        self.check_not_frozen(rt, operator)?;
+        let entry = self.allowance_entry(rt, owner, operator)?;
+        let (allowance, expiry) = match entry {
+            Some(entry) => (entry.amount, entry.expiry),
+            None => (Uint256::default(), None),
+        };
+        if allowance < amount {
+            return Err(TokenError::InsufficientAllowance {
+                owner,
+                operator,
+                allowance,
+                amount,
+            });
+        }
+        if allowance == unlimited_allowance() {
+            return Ok(allowance);
+        }
+        let allowance = allowance - amount;
+        self.set_allowance(rt, owner, operator, allowance.clone(), expiry)?;
+        Ok(allowance)
+    }
+
+    /// Sets the allowance `operator` may spend on behalf of `owner`. Zero allowances are
+    /// deleted, and so is the owner's spender HAMT once it has no entries left.
+    /// Expired entries read as zero and are cleaned up lazily: whenever the owner changes
+    /// one of its allowances, all of its expired ones are pruned on the way. Spends leave
+    /// the rest of the map alone so spenders don't pay for the owner's cleanup.
+    fn set_allowance<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        owner: ActorID,
+        operator: ActorID,
+        amount: Uint256,
+        expiry: Option<ChainEpoch>,
+    ) -> Result<(), TokenError> {
+        let mut owners = load_map::<_, Cid>(rt.store(), &self.allowance)?;
+        let mut spenders = match owners.get(&actor_key(owner))? {
+            Some(spenders) => load_map::<_, AllowanceEntry>(rt.store(), spenders)?,
+            None => Map::new_with_bit_width(rt.store(), HAMT_BIT_WIDTH),
+        };
+
+        if rt.caller() == owner {
+            let epoch = rt.curr_epoch();
+            let mut expired = Vec::new();
+            spenders.for_each(|key, entry| {
+                if entry.expired(epoch) {
+                    expired.push(key.clone());
                 }
-            };
+                Ok(())
+            })?;
+            for key in expired {
+                spenders.delete(&key)?;
+            }
+        }
 
-            TransferLog::new(from, to, amount)
+        if amount == Uint256::default() {
+            spenders.delete(&actor_key(operator))?;
+        } else {
+            spenders.set(actor_key(operator), AllowanceEntry { amount, expiry })?;
         }
+        if spenders.is_empty() {
+            owners.delete(&actor_key(owner))?;
+        } else {
+            owners.set(actor_key(owner), spenders.flush()?)?;
+        }
+        self.allowance = owners.flush()?;
+        Ok(())
+    }
+
+    pub fn increase_allowance<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        operator: ActorID,
+        increase: Uint256,
+    ) -> Result<Uint256, TokenError> {
+        self.check_not_paused()?;
+        let owner = rt.caller();
+        self.check_not_frozen(rt, owner)?;
+        self.check_not_frozen(rt, operator)?;
+        let (current, expiry) = match self.allowance_entry(rt, owner, operator)? {
+            Some(entry) => (entry.amount, entry.expiry),
+            None => (Uint256::default(), None),
+        };
+        // An unlimited allowance can't grow any further and stays unlimited.
+        let allowance = if current == unlimited_allowance() {
+            current
+        } else {
+            current.checked_add(&increase).ok_or(TokenError::Overflow)?
+        };
+        self.set_allowance(rt, owner, operator, allowance.clone(), expiry)?;
+        Ok(allowance)
+    }
+
+    /// Decreases the caller's allowance for `operator`, saturating at zero. Unlimited
+    /// allowances have no amount to decrease from: they can only drop to zero, otherwise
+    /// they must be revoked or re-approved.
+    pub fn decrease_allowance<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        operator: ActorID,
+        decrease: Uint256,
+    ) -> Result<Uint256, TokenError> {
+        let owner = rt.caller();
+        let (current, expiry) = match self.allowance_entry(rt, owner, operator)? {
+            Some(entry) => (entry.amount, entry.expiry),
+            None => (Uint256::default(), None),
+        };
+        if current == unlimited_allowance() && decrease < current {
+            return Err(TokenError::InvalidAmount {
+                amount: decrease,
+                reason: "an unlimited allowance can only be revoked or re-approved",
+            });
+        }
+        let allowance = if current > decrease {
+            current - decrease
+        } else {
+            Uint256::default()
+        };
+        self.set_allowance(rt, owner, operator, allowance.clone(), expiry)?;
+        Ok(allowance)
+    }
+
+    /// Revokes the caller's allowance for `operator`, returning the previous allowance.
+    pub fn revoke_allowance<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        operator: ActorID,
+    ) -> Result<Uint256, TokenError> {
+        let owner = rt.caller();
+        let previous = self.allowance(rt, &owner, &operator)?;
+        self.set_allowance(rt, owner, operator, Uint256::default(), None)?;
+        Ok(previous)
+    }
+
+    /// Destroys `amount` of the caller's own tokens. Unlike `withdraw`, the FIL backing
+    /// them stays in the actor for good.
+    pub fn burn<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        amount: Uint256,
+    ) -> Result<BurnReturn, TokenError> {
+        self.check_not_paused()?;
+        self.check_granularity(&amount)?;
+        let owner = rt.caller();
+        self.debit(rt, owner, amount.clone())?;
+        self.circulating_supply = self.circulating_supply.clone() - amount;
+        Ok(BurnReturn {
+            balance: self.balance_of(rt, owner)?,
+        })
+    }
+
+    /// Destroys `amount` of `owner`'s tokens, spending the caller's allowance.
+    pub fn burn_from<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        owner: ActorID,
+        amount: Uint256,
+    ) -> Result<BurnFromReturn, TokenError> {
+        self.check_not_paused()?;
+        self.check_granularity(&amount)?;
+        let allowance = self.spend_allowance(rt, owner, amount.clone())?;
+        self.debit(rt, owner, amount.clone())?;
+        self.circulating_supply = self.circulating_supply.clone() - amount;
+        Ok(BurnFromReturn {
+            balance: self.balance_of(rt, owner)?,
+            allowance,
+        })
+    }
+
+    /// Destroys `amount` of `owner`'s tokens without an allowance. Only burners may call
+    /// this; frozen accounts aren't exempt, so their funds can be cleared out.
+    pub fn admin_burn<RT: Runtime>(
+        &mut self,
+        rt: &RT,
+        owner: ActorID,
+        amount: Uint256,
+    ) -> Result<BurnReturn, TokenError> {
+        self.require_role(rt, Role::Burner, "burn other holders' tokens")?;
+        self.check_not_paused()?;
+        self.check_granularity(&amount)?;
+        self.take_balance(rt, owner, amount.clone())?;
+        self.circulating_supply = self.circulating_supply.clone() - amount;
+        Ok(BurnReturn {
+            balance: self.balance_of(rt, owner)?,
+        })
     }
 }
@@ -1,98 +1,244 @@
+use crate::role::Role;
 use crate::uint256::Uint256;
+use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::ActorID;
-use serde::{Deserialize, Serialize};
-use serde_json::Error;
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Mint {
-    pub actor: ActorID,
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct ConstructorParams {
+    pub name: String,
+    pub symbol: String,
+    /// Must be 18: the token wraps FIL one attoFIL per base unit, so this isn't configurable.
+    pub decimals: u64,
+    pub max_supply: Uint256,
+    pub owner: Address,
+    /// Start out in allowlist-only mode.
+    pub allowlist_only: bool,
+    /// Tokens minted when the actor is created. May be empty.
+    pub initial_allocations: Vec<Allocation>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct Allocation {
+    pub to: Address,
     pub amount: Uint256,
 }
 
-impl Mint {
-    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
-        serde_json::from_slice::<Self>(data)
-    }
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct Mint {
+    pub actor: Address,
+    pub amount: Uint256,
+    pub operator_data: RawBytes,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
 pub struct Transfer {
-    pub to: ActorID,
+    pub to: Address,
     pub amount: Uint256,
+    pub operator_data: RawBytes,
 }
 
-impl Transfer {
-    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
-        serde_json::from_slice::<Self>(data)
-    }
-}
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
 pub struct Approve {
-    pub actor: ActorID,
+    pub actor: Address,
     pub amount: Uint256,
+    /// Last epoch the allowance can be spent in, if it should lapse.
+    pub expiry: Option<ChainEpoch>,
 }
 
-impl Approve {
-    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
-        serde_json::from_slice::<Self>(data)
-    }
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct ApproveIf {
+    pub actor: Address,
+    /// The allowance the caller believes is current.
+    pub expected: Uint256,
+    pub amount: Uint256,
+    pub expiry: Option<ChainEpoch>,
 }
-#[derive(Serialize, Deserialize, Debug)]
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
 pub struct Allowance {
-    pub from: ActorID,
-    pub to: ActorID,
+    pub owner: Address,
+    pub operator: Address,
 }
 
-impl Allowance {
-    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
-        serde_json::from_slice::<Self>(data)
-    }
-}
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
 pub struct TransferFrom {
-    pub from: ActorID,
-    pub to: ActorID,
+    pub owner: Address,
+    pub to: Address,
     pub amount: Uint256,
+    pub operator_data: RawBytes,
 }
 
-impl TransferFrom {
-    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
-        serde_json::from_slice::<Self>(data)
-    }
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct IncreaseAllowance {
+    pub operator: Address,
+    pub increase: Uint256,
 }
-#[derive(Serialize, Deserialize, Debug)]
-pub struct MintLog {
-    actor: ActorID,
-    amount: Uint256,
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct DecreaseAllowance {
+    pub operator: Address,
+    pub decrease: Uint256,
 }
 
-impl MintLog {
-    pub fn new(actor: ActorID, amount: Uint256) -> Self {
-        Self {
-            actor: actor,
-            amount: amount,
-        }
-    }
-    pub fn to_bytes(&self) -> Vec<u8> {
-        serde_json::to_vec(self).unwrap()
-    }
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct RevokeAllowance {
+    pub operator: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct Burn {
+    pub amount: Uint256,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct BurnFrom {
+    pub owner: Address,
+    pub amount: Uint256,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct AdminBurn {
+    pub owner: Address,
+    pub amount: Uint256,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct SetMetadata {
+    pub name: String,
+    pub symbol: String,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct Withdraw {
+    pub amount: Uint256,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct AddMinter {
+    pub minter: Address,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct TransferLog {
-    from: ActorID,
-    to: ActorID,
-    amount: Uint256,
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct RemoveMinter {
+    pub minter: Address,
 }
 
-impl TransferLog {
-    pub fn new(from: ActorID, to: ActorID, amount: Uint256) -> Self {
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct ProposeOwner {
+    pub nominee: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct Freeze {
+    pub account: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct Unfreeze {
+    pub account: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct SetAllowlistOnly {
+    pub enabled: bool,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct AddToAllowlist {
+    pub account: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct RemoveFromAllowlist {
+    pub account: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct GrantRole {
+    pub role: Role,
+    pub account: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct RevokeRole {
+    pub role: Role,
+    pub account: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct RenounceRole {
+    pub role: Role,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct HasRole {
+    pub role: Role,
+    pub account: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct MintReturn {
+    pub balance: Uint256,
+    pub supply: Uint256,
+    pub recipient_data: RawBytes,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct TransferReturn {
+    pub from_balance: Uint256,
+    pub to_balance: Uint256,
+    pub recipient_data: RawBytes,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct TransferFromReturn {
+    pub from_balance: Uint256,
+    pub to_balance: Uint256,
+    pub allowance: Uint256,
+    pub recipient_data: RawBytes,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct PausedReturn {
+    pub paused: bool,
+    /// Epoch the paused flag last changed at.
+    pub changed_at: ChainEpoch,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct BurnReturn {
+    pub balance: Uint256,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct BurnFromReturn {
+    pub balance: Uint256,
+    pub allowance: Uint256,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+pub struct ApproveLog {
+    pub owner: ActorID,
+    pub spender: ActorID,
+    pub amount: Uint256,
+    pub expiry: Option<ChainEpoch>,
+}
+
+impl ApproveLog {
+    pub fn new(
+        owner: ActorID,
+        spender: ActorID,
+        amount: Uint256,
+        expiry: Option<ChainEpoch>,
+    ) -> Self {
         Self {
-            from: from,
-            to: to,
-            amount: amount,
+            owner,
+            spender,
+            amount,
+            expiry,
         }
     }
-    pub fn to_bytes(&self) -> Vec<u8> {
-        serde_json::to_vec(self).unwrap()
-    }
 }
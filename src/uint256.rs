@@ -1,13 +1,16 @@
-use anyhow::Error;
-use fvm_ipld_encoding::tuple::{Deserialize_tuple, Serialize_tuple};
-use fvm_shared::bigint::BigUint;
-use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
-use serde::ser::{Serialize, SerializeStruct, Serializer};
+use fvm_shared::bigint::{biguint_ser, BigUint};
+use fvm_shared::econ::TokenAmount;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
-use std::marker::Copy;
 use std::ops::{Add, Sub};
-#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+
+/// Token amount, encoded on the wire as a Filecoin CBOR bignum. Decoding rejects values
+/// wider than 256 bits.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, PartialOrd)]
+#[serde(transparent)]
 pub struct Uint256 {
+    #[serde(with = "biguint_ser")]
     pub big_uint: BigUint,
 }
 
@@ -17,128 +20,67 @@ impl Uint256 {
             big_uint: BigUint::from_bytes_be(bytes),
         }
     }
-    pub fn to_bytes_be(&self) -> Vec<u8> {
-        self.big_uint.to_bytes_be()
-    }
 
-    pub fn to_string(&self) -> String {
-        self.big_uint.to_string()
+    /// The largest 256-bit value, `2^256 - 1`.
+    pub fn max_value() -> Self {
+        Self::from_bytes_be(&[0xff; 32])
     }
-}
-
-impl Add for Uint256 {
-    type Output = Uint256;
 
-    fn add(self, rhs: Self) -> Self::Output {
-        return Uint256 {
-            big_uint: self.big_uint + rhs.big_uint,
-        };
+    /// Adds `other`, returning `None` if the sum no longer fits in 256 bits.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let big_uint = &self.big_uint + &other.big_uint;
+        if big_uint.bits() > 256 {
+            return None;
+        }
+        Some(Self { big_uint })
     }
-}
 
-impl Sub for Uint256 {
-    type Output = Self;
-
-    fn sub(self, other: Self) -> Self::Output {
-        Self {
-            big_uint: self.big_uint - other.big_uint,
+    /// Converts a FIL amount in attoFIL, returning `None` if it's negative or too large.
+    pub fn from_token_amount(amount: &TokenAmount) -> Option<Self> {
+        let big_uint = amount.to_biguint()?;
+        if big_uint.bits() > 256 {
+            return None;
         }
+        Some(Self { big_uint })
     }
-}
 
-impl Serialize for Uint256 {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut ts = serializer.serialize_struct("Uint256", 1)?;
-        ts.serialize_field("big_uint", &self.big_uint.to_bytes_le())?;
-        ts.end()
+    pub fn to_token_amount(&self) -> TokenAmount {
+        TokenAmount::from(self.big_uint.clone())
     }
 }
 
 impl<'de> Deserialize<'de> for Uint256 {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        enum Field {
-            Big_Uint,
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let big_uint = biguint_ser::deserialize(deserializer)?;
+        if big_uint.bits() > 256 {
+            return Err(D::Error::custom("amount overflows 256 bits"));
         }
+        Ok(Self { big_uint })
+    }
+}
 
-        impl<'de> Deserialize<'de> for Field {
-            fn deserialize<D>(deserializer: D) -> Result<Field, D::Error>
-            where
-                D: Deserializer<'de>,
-            {
-                struct FieldVisitor;
-
-                impl<'de> Visitor<'de> for FieldVisitor {
-                    type Value = Field;
-
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("big_uint")
-                    }
+impl fmt::Display for Uint256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.big_uint.fmt(f)
+    }
+}
 
-                    fn visit_str<E>(self, value: &str) -> Result<Field, E>
-                    where
-                        E: de::Error,
-                    {
-                        match value {
-                            "big_uint" => Ok(Field::Big_Uint),
-                            _ => Err(de::Error::unknown_field(value, FIELDS)),
-                        }
-                    }
-                }
+impl Add for Uint256 {
+    type Output = Uint256;
 
-                deserializer.deserialize_identifier(FieldVisitor)
-            }
+    fn add(self, rhs: Self) -> Self::Output {
+        Uint256 {
+            big_uint: self.big_uint + rhs.big_uint,
         }
+    }
+}
 
-        struct Uint256Visitor;
-
-        impl<'de> Visitor<'de> for Uint256Visitor {
-            type Value = Uint256;
-
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct Uint256")
-            }
-
-            fn visit_seq<V>(self, mut seq: V) -> Result<Uint256, V::Error>
-            where
-                V: SeqAccess<'de>,
-            {
-                let big_uint = seq
-                    .next_element()?
-                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
-                let big_uint = BigUint::from_bytes_le(big_uint);
-                Ok(Uint256 { big_uint: big_uint })
-            }
-
-            fn visit_map<V>(self, mut map: V) -> Result<Uint256, V::Error>
-            where
-                V: MapAccess<'de>,
-            {
-                let mut big_uint = None;
-                if let Some(key) = map.next_key()? {
-                    match key {
-                        Field::Big_Uint => {
-                            if big_uint.is_some() {
-                                return Err(de::Error::duplicate_field("big_uint"));
-                            }
-                            let value = BigUint::from_bytes_le(&map.next_value::<Vec<u8>>()?);
-                            big_uint = Some(value);
-                        }
-                    }
-                }
-
-                let big_uint = big_uint.ok_or_else(|| de::Error::missing_field("big_uint"))?;
+impl Sub for Uint256 {
+    type Output = Self;
 
-                Ok(Uint256 { big_uint: big_uint })
-            }
+    fn sub(self, other: Self) -> Self::Output {
+        Self {
+            big_uint: self.big_uint - other.big_uint,
         }
-
-        const FIELDS: &'static [&'static str] = &["big_uint"];
-        deserializer.deserialize_struct("Uint256", FIELDS, Uint256Visitor)
     }
 }